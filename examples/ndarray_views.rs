@@ -0,0 +1,28 @@
+extern crate isosurface;
+extern crate ndarray;
+
+use isosurface::{marching_tetrahedra_array, to_array2, Options};
+use ndarray::{s, Array, ShapeBuilder};
+
+fn main() {
+    let n = 21;
+    let c = (n - 1) as f64 / 2.;
+
+    // a volume in column-major (Fortran) order
+    let u = Array::from_shape_fn((n, n, n).f(), |(i, j, k)| {
+        let (x, y, z) = (i as f64 - c, j as f64 - c, k as f64 - c);
+        (x * x + y * y + z * z).sqrt() - 8.
+    });
+    let data = Array::from_elem((n, n, n), ());
+
+    // every other node of the reversed volume, without copying
+    let u = u.slice(s![..;-2, ..;2, ..;2]);
+    let data = data.slice(s![..;2, ..;2, ..;2]);
+
+    let mesh = marching_tetrahedra_array(u, 0., data, &Options::default());
+
+    let verts = to_array2(&mesh.verts);
+    let faces = to_array2(&mesh.faces);
+    println!("vertices: {:?}", verts.dim());
+    println!("faces: {:?}", faces.dim());
+}
@@ -23,9 +23,9 @@ fn main() {
 
     println!("{}", u);
 
-    let (verts, faces, normals) = marching_tetrahedra(u.as_slice().unwrap(), dim, 0.);
+    let mesh = marching_tetrahedra(u.as_slice().unwrap(), dim, 0.);
 
-    println!("{:?}", verts);
-    println!("{:?}", faces);
-    println!("{:?}", normals);
+    println!("{:?}", mesh.verts);
+    println!("{:?}", mesh.faces);
+    println!("{:?}", mesh.normals);
 }
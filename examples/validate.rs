@@ -0,0 +1,35 @@
+extern crate isosurface;
+extern crate ndarray;
+
+use isosurface::{marching_tetrahedra, validate_mesh};
+use ndarray::Array;
+
+fn main() {
+    let n = 16;
+    let xs = Array::linspace(-0.5f64, 0.5, n);
+
+    let dim = (n, n, n);
+
+    let u = {
+        let mut u = Array::from_elem(dim, 0.);
+
+        for ((i, j, k), u) in u.indexed_iter_mut() {
+            let (x, y, z) = (xs[i], xs[j], xs[k]);
+            *u = (x * x + y * y + z * z).sqrt();
+        }
+        u
+    };
+
+    let mesh = marching_tetrahedra(u.as_slice().unwrap(), dim, 0.3);
+    let report = validate_mesh(&mesh.verts, &mesh.faces, 0.01);
+
+    println!("{} vertices, {} faces", mesh.verts.len(), mesh.faces.len());
+    println!("duplicate vertices: {}", report.duplicate_vertices);
+    println!("boundary edges: {}", report.boundary_edges.len());
+    println!("non-manifold edges: {}", report.non_manifold_edges.len());
+    println!("non-manifold vertices: {}", report.non_manifold_vertices.len());
+    println!("inconsistently oriented edges: {}", report.inconsistent_edges.len());
+    println!("degenerate faces: {}", report.degenerate_faces.len());
+    println!("watertight: {}", report.is_watertight());
+    println!("valid: {}", report.is_valid());
+}
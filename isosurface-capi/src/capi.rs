@@ -15,7 +15,8 @@ ffi_fn! {
         -> *const Isosurface {
         let u = unsafe { slice::from_raw_parts(u, ni * nj * nk) };
 
-        let (verts, faces, normals) = isosurface::marching_tetrahedra(u, (ni, nj, nk), level);
+        let (verts, faces, normals, _) =
+            isosurface::marching_tetrahedra(u, (ni, nj, nk), level).into_raw_parts();
 
         let iso = Isosurface {
             verts, faces, normals, data: None,
@@ -79,6 +80,6 @@ ffi_fn! {
 
 ffi_fn! {
     fn iso_free(re: *const Isosurface) {
-        unsafe { Box::from_raw(re as *mut Isosurface); }
+        unsafe { drop(Box::from_raw(re as *mut Isosurface)); }
     }
 }
@@ -1,16 +1,39 @@
+# Vertices of a positively oriented tetrahedron, used to orient the emitted faces.
+tet = [(0, 0, 0), (1, 0, 0), (0, 1, 0), (0, 0, 1)]
+
+
+def sub(a, b):
+    return [x - y for x, y in zip(a, b)]
+
+
+def cross(a, b):
+    return [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+
+
+def dot(a, b):
+    return sum(x * y for x, y in zip(a, b))
+
+
+def orient(face, verts, grad):
+    """Orients the face so that its normal points in the direction of the gradient."""
+    a, b, c = (verts[i] for i in face)
+    if dot(cross(sub(b, a), sub(c, a)), grad) < 0:
+        return [face[0], face[2], face[1]]
+    return face
+
+
 def recur(idx):
     n = len(idx)
     indent = ' ' * (4 * n)
     if len(idx) < 4:
         print(indent + f'if u{n} >= zero {{')
-        if len(idx) < 3:
-            print(indent + f'    let u{n} = u{n}.nudge();')
         idx.append(0)
         recur(idx)
         idx.pop()
-        print(indent + '} else {')
         idx.append(1)
-        recur(idx)
+        if sum(idx) < 4:
+            print(indent + '} else {')
+            recur(idx)
         idx.pop()
         print(indent + '}')
         return
@@ -28,15 +51,22 @@ def recur(idx):
         else:
             zero.append(i)
 
+    # the vertices are at the midpoints of the edges for u = -1 below and u = 1 above the level
+    u = [-1 if i in one else 1 for i in range(0, 4)]
+    grad = [u[1] - u[0], u[2] - u[0], u[3] - u[0]]
+    verts = []
     for i in one:
         for j in zero:
             print(indent + f'emit_vertex(v{i}.interpolate(&v{j}, u{i}, u{j}));')
+            verts.append([(x + y) / 2 for x, y in zip(tet[i], tet[j])])
 
     if n == 2:
-        print(indent + 'emit_face([0, 1, 2]);')
-        print(indent + 'emit_face([2, 1, 3]);')
-    elif n == 1 or n == 3:
-        print(indent + 'emit_face([0, 1, 2]);')
+        faces = [[0, 1, 2], [2, 1, 3]]
+    else:
+        faces = [[0, 1, 2]]
+    for f in faces:
+        f = orient(f, verts, grad)
+        print(indent + f'emit_face([{f[0]}, {f[1]}, {f[2]}]);')
 
 print('// START GENERATED: generated by `scripts/tetrahedron_cuts.py`')
 recur([])
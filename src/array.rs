@@ -0,0 +1,71 @@
+//! Support for `ndarray` arrays.
+
+use crate::interpolate::Interpolate;
+use crate::isoline::{marching_triangles_strided, Isoline, IsolineOptions};
+use crate::isosurface::{marching_tetrahedra_strided, marching_tetrahedra_welded_strided, Options};
+use crate::mesh::Mesh;
+use crate::scalar::Scalar;
+use crate::view::StridedView;
+use ndarray::{Array2, ArrayView, Dimension};
+
+impl<'a, T, D, const N: usize> From<ArrayView<'a, T, D>> for StridedView<'a, T, N>
+where
+    D: Dimension,
+{
+    /// Panics if the array does not have `N` dimensions.
+    fn from(a: ArrayView<'a, T, D>) -> StridedView<'a, T, N> {
+        assert_eq!(a.ndim(), N, "wrong number of dimensions");
+        let mut shape = [0; N];
+        let mut strides = [0; N];
+        shape.copy_from_slice(a.shape());
+        strides.copy_from_slice(a.strides());
+
+        // SAFETY: the elements of the array view are valid for reads for `'a`
+        unsafe { StridedView::from_raw_parts(a.as_ptr(), shape, strides) }
+    }
+}
+
+/// As `marching_tetrahedra_strided`, with `u` and `data` given by `ndarray` array views of any
+/// memory layout.
+pub fn marching_tetrahedra_array<D, T>(
+    u: ndarray::ArrayView3<D>,
+    level: D,
+    data: ndarray::ArrayView3<T>,
+    options: &Options<'_, D>,
+) -> Mesh<D, T>
+where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    marching_tetrahedra_strided(u.into(), level, data.into(), options)
+}
+
+/// As `marching_tetrahedra_welded_strided`, with `u` and `data` given by `ndarray` array views
+/// of any memory layout.
+pub fn marching_tetrahedra_welded_array<D, T>(
+    u: ndarray::ArrayView3<D>,
+    level: D,
+    data: ndarray::ArrayView3<T>,
+    options: &Options<'_, D>,
+) -> Mesh<D, T>
+where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    marching_tetrahedra_welded_strided(u.into(), level, data.into(), options)
+}
+
+/// As `marching_triangles_strided`, with `u` given by an `ndarray` array view of any memory
+/// layout.
+pub fn marching_triangles_array<D: Scalar>(
+    u: ndarray::ArrayView2<D>,
+    level: D,
+    options: &IsolineOptions,
+) -> Isoline<D> {
+    marching_triangles_strided(u.into(), level, options)
+}
+
+/// Collects vertices, faces or normals into a matrix with one row per element.
+pub fn to_array2<T: Clone, const N: usize>(rows: &[[T; N]]) -> Array2<T> {
+    Array2::from_shape_fn((rows.len(), N), |(i, j)| rows[i][j].clone())
+}
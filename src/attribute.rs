@@ -0,0 +1,154 @@
+//! Description of the data of the vertices, shared by the output formats.
+
+use std::io::{self, Write};
+
+/// Encoding of a PLY file, also used for the values of the other formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// Type of a scalar property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlyType {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+/// Value of a scalar property.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlyValue {
+    Char(i8),
+    UChar(u8),
+    Short(i16),
+    UShort(u16),
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+    Double(f64),
+}
+
+impl PlyValue {
+    pub fn to_f64(self) -> f64 {
+        match self {
+            PlyValue::Char(x) => x.into(),
+            PlyValue::UChar(x) => x.into(),
+            PlyValue::Short(x) => x.into(),
+            PlyValue::UShort(x) => x.into(),
+            PlyValue::Int(x) => x.into(),
+            PlyValue::UInt(x) => x.into(),
+            PlyValue::Float(x) => x.into(),
+            PlyValue::Double(x) => x,
+        }
+    }
+
+    pub(crate) fn write<W: Write>(self, w: &mut W, format: PlyFormat) -> io::Result<()> {
+        macro_rules! write_value {
+            ($x:expr) => {
+                match format {
+                    PlyFormat::Ascii => write!(w, "{}", $x),
+                    PlyFormat::BinaryLittleEndian => w.write_all(&$x.to_le_bytes()),
+                    PlyFormat::BinaryBigEndian => w.write_all(&$x.to_be_bytes()),
+                }
+            };
+        }
+
+        match self {
+            PlyValue::Char(x) => write_value!(x),
+            PlyValue::UChar(x) => write_value!(x),
+            PlyValue::Short(x) => write_value!(x),
+            PlyValue::UShort(x) => write_value!(x),
+            PlyValue::Int(x) => write_value!(x),
+            PlyValue::UInt(x) => write_value!(x),
+            PlyValue::Float(x) => write_value!(x),
+            PlyValue::Double(x) => write_value!(x),
+        }
+    }
+}
+
+/// Data that can be written as vertex properties, such as the PLY properties, the VTK point
+/// data arrays or the glTF attributes.
+///
+/// Implemented for the primitive numeric types, which give a single property, and for arrays
+/// and tuples of them, which give a property for every element. Implement it for custom data
+/// types to name their properties.
+pub trait PlyProperty {
+    /// Appends the names and types of the properties to `props`.
+    ///
+    /// `name` is the name given to the data, to be used for a single property or as a prefix.
+    fn describe(name: &str, props: &mut Vec<(String, PlyType)>);
+
+    /// Appends the values of the properties to `values`, in the order of `describe`.
+    fn values(&self, values: &mut Vec<PlyValue>);
+}
+
+macro_rules! impl_ply_property {
+    ($t:ty, $ty:ident) => {
+        impl PlyProperty for $t {
+            fn describe(name: &str, props: &mut Vec<(String, PlyType)>) {
+                props.push((name.to_string(), PlyType::$ty));
+            }
+
+            fn values(&self, values: &mut Vec<PlyValue>) {
+                values.push(PlyValue::$ty(*self));
+            }
+        }
+    };
+}
+
+impl_ply_property!(i8, Char);
+impl_ply_property!(u8, UChar);
+impl_ply_property!(i16, Short);
+impl_ply_property!(u16, UShort);
+impl_ply_property!(i32, Int);
+impl_ply_property!(u32, UInt);
+impl_ply_property!(f32, Float);
+impl_ply_property!(f64, Double);
+
+impl PlyProperty for () {
+    fn describe(_name: &str, _props: &mut Vec<(String, PlyType)>) {}
+
+    fn values(&self, _values: &mut Vec<PlyValue>) {}
+}
+
+/// The elements are named `name_0`, `name_1`, ...
+impl<P: PlyProperty, const N: usize> PlyProperty for [P; N] {
+    fn describe(name: &str, props: &mut Vec<(String, PlyType)>) {
+        for i in 0..N {
+            P::describe(&format!("{name}_{i}"), props);
+        }
+    }
+
+    fn values(&self, values: &mut Vec<PlyValue>) {
+        for p in self {
+            p.values(values);
+        }
+    }
+}
+
+macro_rules! impl_ply_property_tuple {
+    ($($p:ident $i:tt),*) => {
+        /// The elements are named `name_0`, `name_1`, ...
+        impl<$($p: PlyProperty),*> PlyProperty for ($($p,)*) {
+            fn describe(name: &str, props: &mut Vec<(String, PlyType)>) {
+                $($p::describe(&format!("{name}_{}", $i), props);)*
+            }
+
+            fn values(&self, values: &mut Vec<PlyValue>) {
+                $(self.$i.values(values);)*
+            }
+        }
+    };
+}
+
+impl_ply_property_tuple!(A 0, B 1);
+impl_ply_property_tuple!(A 0, B 1, C 2);
+impl_ply_property_tuple!(A 0, B 1, C 2, D 3);
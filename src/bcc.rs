@@ -0,0 +1,266 @@
+use crate::decomposition::Tetrahedron;
+use crate::interpolate::Interpolate;
+use crate::isosurface::tetrahedron;
+use crate::mesh::Mesh;
+use crate::scalar::Scalar;
+use crate::sink::MeshSink;
+
+/// Nodes of the BCC lattice around a cell: the cell corners `0..8` numbered as in
+/// `Decomposition`, the cell center `CENTER`, and the centers of the next cells along the _x_,
+/// _y_ and _z_ axis, `CENTER + 1 + axis`.
+const CENTER: usize = 8;
+
+/// The BCC tetrahedra of a cell.
+struct BccTetrahedra {
+    /// For each axis, the four tetrahedra between the cell center, the center of the next cell
+    /// along the axis, and an edge of the common face.
+    inner: [Vec<Tetrahedron>; 3],
+    /// For each axis, the two tetrahedra between the cell center and the cell face at the lower
+    /// and the upper end of the grid.
+    lower: [Vec<Tetrahedron>; 3],
+    upper: [Vec<Tetrahedron>; 3],
+}
+
+impl BccTetrahedra {
+    fn new() -> BccTetrahedra {
+        let pos = |n: usize| -> [f64; 3] {
+            if n < CENTER {
+                [(n >> 2 & 1) as f64, (n >> 1 & 1) as f64, (n & 1) as f64]
+            } else {
+                let mut p = [0.5; 3];
+                if n > CENTER {
+                    p[n - CENTER - 1] += 1.;
+                }
+                p
+            }
+        };
+        let tet = |corners: [usize; 4]| Tetrahedron::with_positions(corners, corners.map(pos));
+
+        // corners of the lower face orthogonal to the axis, in cyclic order
+        let face = |axis: usize| {
+            let b = 1 << (2 - (axis + 1) % 3);
+            let c = 1 << (2 - (axis + 2) % 3);
+            [0, b, b | c, c]
+        };
+        let upper_face = |axis: usize| face(axis).map(|c| c | 1 << (2 - axis));
+
+        BccTetrahedra {
+            inner: [0, 1, 2].map(|axis| {
+                let f = upper_face(axis);
+                (0..4)
+                    .map(|m| tet([CENTER, CENTER + 1 + axis, f[m], f[(m + 1) % 4]]))
+                    .collect()
+            }),
+            lower: [0, 1, 2].map(|axis| {
+                let f = face(axis);
+                vec![
+                    tet([CENTER, f[0], f[1], f[2]]),
+                    tet([CENTER, f[0], f[2], f[3]]),
+                ]
+            }),
+            upper: [0, 1, 2].map(|axis| {
+                let f = upper_face(axis);
+                vec![
+                    tet([CENTER, f[0], f[1], f[2]]),
+                    tet([CENTER, f[0], f[2], f[3]]),
+                ]
+            }),
+        }
+    }
+}
+
+/// Finds the isosurface at `level` of a function sampled on a body-centered cubic (BCC) lattice
+/// using the marching tetrahedra algorithm.
+///
+/// The lattice consists of the regular grid of dimension `dim` with values `u`, as in
+/// `marching_tetrahedra`, and of the centers of its cells with values `centers`. The center of
+/// the cell `(i, j, k)` has coordinates `(i + 0.5, j + 0.5, k + 0.5)`, and `centers` is in
+/// _row-major order_ with dimension `(dim.0 - 1, dim.1 - 1, dim.2 - 1)`. `data` and
+/// `center_data` are the data at the grid nodes and at the cell centers.
+///
+/// The domain is split into the tetrahedra of the BCC lattice: every face shared by two cells
+/// gives four tetrahedra with vertices at the two cell centers and at an edge of the face. These
+/// are much better shaped than the tetrahedra of the cell decompositions. Faces on the boundary
+/// of the grid are split into two triangles, each forming a tetrahedron with the cell center.
+///
+/// Returns the same output as `marching_tetrahedra_with_data`.
+pub fn marching_tetrahedra_bcc<D, T>(
+    u: &[D],
+    centers: &[D],
+    dim: (usize, usize, usize),
+    level: D,
+    data: &[T],
+    center_data: &[T],
+) -> Mesh<D, T>
+where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    let mut mesh = Mesh::new();
+    marching_tetrahedra_bcc_sink(u, centers, dim, level, data, center_data, &mut mesh);
+    mesh
+}
+
+/// As `marching_tetrahedra_bcc`, but the mesh is passed to `sink` instead of returned.
+pub fn marching_tetrahedra_bcc_sink<D, T, K>(
+    u: &[D],
+    centers: &[D],
+    dim: (usize, usize, usize),
+    level: D,
+    data: &[T],
+    center_data: &[T],
+    sink: &mut K,
+) where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+    K: MeshSink<D, T> + ?Sized,
+{
+    let (ni, nj, nk) = dim;
+    assert_eq!(ni * nj * nk, u.len());
+    assert_eq!(ni * nj * nk, data.len());
+    let (ci, cj, ck) = (
+        ni.saturating_sub(1),
+        nj.saturating_sub(1),
+        nk.saturating_sub(1),
+    );
+    assert_eq!(ci * cj * ck, centers.len());
+    assert_eq!(ci * cj * ck, center_data.len());
+
+    let mut verts: Vec<([D; 3], T)> = Vec::with_capacity(4);
+    let mut faces: Vec<[u32; 3]> = Vec::with_capacity(2);
+
+    let tets = BccTetrahedra::new();
+
+    let strides = [nj * nk, nk, 1];
+    let center_strides = [cj * ck, ck, 1];
+
+    sink.begin();
+
+    for i in 0..ci {
+        for j in 0..cj {
+            for k in 0..ck {
+                let idx = [i, j, k];
+                let s = i * strides[0] + j * strides[1] + k;
+                let cs = i * center_strides[0] + j * center_strides[1] + k;
+                let ps = [D::from(i as f32), D::from(j as f32), D::from(k as f32)];
+
+                // value, position and data at a node of the lattice
+                let node = |n: usize| {
+                    if n < CENTER {
+                        let vi = s
+                            + (n >> 2 & 1) * strides[0]
+                            + (n >> 1 & 1) * strides[1]
+                            + (n & 1) * strides[2];
+                        let vp = [
+                            ps[0] + D::from((n >> 2 & 1) as f32),
+                            ps[1] + D::from((n >> 1 & 1) as f32),
+                            ps[2] + D::from((n & 1) as f32),
+                        ];
+                        (u[vi] - level, vp, data[vi])
+                    } else {
+                        let mut vi = cs;
+                        let mut vp = ps.map(|p| p + D::from(0.5));
+                        if n > CENTER {
+                            let axis = n - CENTER - 1;
+                            vi += center_strides[axis];
+                            vp[axis] = vp[axis] + D::from(1.);
+                        }
+                        (centers[vi] - level, vp, center_data[vi])
+                    }
+                };
+
+                let cell_tets = (0..3).flat_map(|axis| {
+                    let mut ts: Vec<&Tetrahedron> = Vec::new();
+                    if idx[axis] == 0 {
+                        ts.extend(&tets.lower[axis]);
+                    }
+                    if idx[axis] + 1 < [ci, cj, ck][axis] {
+                        ts.extend(&tets.inner[axis]);
+                    } else {
+                        ts.extend(&tets.upper[axis]);
+                    }
+                    ts
+                });
+
+                for tet in cell_tets {
+                    let nodes = tet.corners.map(node);
+                    let us = nodes.map(|n| n.0);
+
+                    verts.clear();
+                    faces.clear();
+                    tetrahedron(
+                        us,
+                        nodes.map(|n| (n.1, n.2)),
+                        |v| verts.push(v),
+                        |f| faces.push(tet.orient(f)),
+                    );
+
+                    // normals
+                    let n = tet.gradient(us);
+
+                    let mut idx = [0; 4];
+                    for (&(v, d), idx) in verts.iter().zip(&mut idx) {
+                        *idx = sink.vertex(v, n, d);
+                    }
+                    for f in &faces {
+                        sink.face(f.map(|v| idx[v as usize]));
+                    }
+                }
+            }
+        }
+    }
+
+    sink.end();
+}
+
+/// As `marching_tetrahedra_bcc`, but the values and the data at the cell centers are the
+/// averages of those at the cell corners, so that only the regular grid needs to be sampled.
+pub fn marching_tetrahedra_bcc_resampled<D, T>(
+    u: &[D],
+    dim: (usize, usize, usize),
+    level: D,
+    data: &[T],
+) -> Mesh<D, T>
+where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    let (ni, nj, nk) = dim;
+    assert_eq!(ni * nj * nk, u.len());
+    assert_eq!(ni * nj * nk, data.len());
+
+    let centers = cell_averages(u, dim);
+    let center_data = cell_averages(data, dim);
+
+    marching_tetrahedra_bcc(u, &centers, dim, level, data, &center_data)
+}
+
+/// Averages of the values at the corners of every cell of the grid.
+fn cell_averages<D, T>(values: &[T], dim: (usize, usize, usize)) -> Vec<T>
+where
+    D: From<f32>,
+    T: Interpolate<D> + Copy,
+{
+    let (ni, nj, nk) = dim;
+    let strides = [nj * nk, nk, 1];
+    // interpolating between values -1 and 1 gives the midpoint
+    let mid = |a: T, b: T| a.interpolate(&b, D::from(-1.), D::from(1.));
+
+    let mut r =
+        Vec::with_capacity(ni.saturating_sub(1) * nj.saturating_sub(1) * nk.saturating_sub(1));
+    for i in 1..ni {
+        for j in 1..nj {
+            for k in 1..nk {
+                let s = (i - 1) * strides[0] + (j - 1) * strides[1] + (k - 1);
+                let c = |n: usize| {
+                    values[s + (n >> 2 & 1) * strides[0] + (n >> 1 & 1) * strides[1] + (n & 1)]
+                };
+                r.push(mid(
+                    mid(mid(c(0), c(1)), mid(c(2), c(3))),
+                    mid(mid(c(4), c(5)), mid(c(6), c(7))),
+                ));
+            }
+        }
+    }
+    r
+}
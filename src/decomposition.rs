@@ -0,0 +1,144 @@
+/// Splitting of a grid cell into tetrahedra.
+///
+/// The corners of a cell are numbered so that the bits of the corner index, from the most
+/// significant one, give the offset of the corner along the _x_, _y_ and _z_ axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Decomposition {
+    /// Six tetrahedra sharing the diagonal from corner 0 to corner 7 (the Kuhn split).
+    #[default]
+    Six,
+    /// Six tetrahedra sharing the diagonal from corner 1 to corner 6, the Kuhn split mirrored
+    /// along the _z_ axis.
+    SixRotated,
+    /// Four tetrahedra cut off at the corners and one in the center.
+    ///
+    /// The split alternates between neighboring cells so that their common faces are split along
+    /// the same diagonals: cells with an even sum of indices cut off corners 0, 3, 5 and 6, odd
+    /// cells cut off corners 1, 2, 4 and 7.
+    Five,
+}
+
+/// A tetrahedron of a cell decomposition.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Tetrahedron {
+    /// The cell corners, or other nodes in the case of the BCC lattice.
+    pub corners: [usize; 4],
+    /// Coefficients of the values at the corners in the gradient of the linear interpolant.
+    pub gradient: [[f32; 4]; 3],
+    /// Whether the corners are positively oriented.
+    pub positive: bool,
+}
+
+impl Decomposition {
+    /// Tetrahedra of a cell; `odd` is the parity of the sum of the cell indices.
+    pub(crate) fn tetrahedra(self, odd: bool) -> Vec<Tetrahedron> {
+        let corners: Vec<[usize; 4]> = match self {
+            Decomposition::Six | Decomposition::SixRotated => {
+                // permutations of [0, 1, 2]
+                let perms = [
+                    [0, 1, 2],
+                    [0, 2, 1],
+                    [1, 0, 2],
+                    [1, 2, 0],
+                    [2, 0, 1],
+                    [2, 1, 0],
+                ];
+                let mirror = if self == Decomposition::SixRotated { 1 } else { 0 };
+
+                // walk along the edges of a cube in the order given by the permutation
+                perms
+                    .iter()
+                    .map(|perm| {
+                        let mut c = [0; 4];
+                        for m in 0..3 {
+                            c[m + 1] = c[m] + (1 << (2 - perm[m]));
+                        }
+                        c.map(|c| c ^ mirror)
+                    })
+                    .collect()
+            }
+            Decomposition::Five => {
+                let (cut, center) = if odd {
+                    ([1, 2, 4, 7], [0, 3, 5, 6])
+                } else {
+                    ([0, 3, 5, 6], [1, 2, 4, 7])
+                };
+                cut.iter()
+                    .map(|&c| [c, c ^ 4, c ^ 2, c ^ 1])
+                    .chain(Some(center))
+                    .collect()
+            }
+        };
+
+        corners.into_iter().map(Tetrahedron::new).collect()
+    }
+}
+
+impl Tetrahedron {
+    fn new(corners: [usize; 4]) -> Tetrahedron {
+        let pos = |c: usize| [(c >> 2 & 1) as f64, (c >> 1 & 1) as f64, (c & 1) as f64];
+
+        Tetrahedron::with_positions(corners, corners.map(pos))
+    }
+
+    /// A tetrahedron with corners at the given positions, in the units of the grid.
+    pub fn with_positions(corners: [usize; 4], pos: [[f64; 3]; 4]) -> Tetrahedron {
+        // the edges from the first corner
+        let p0 = pos[0];
+        let e = [1, 2, 3].map(|m| {
+            let p = pos[m];
+            [p[0] - p0[0], p[1] - p0[1], p[2] - p0[2]]
+        });
+        let cross = |a: [f64; 3], b: [f64; 3]| {
+            [
+                a[1] * b[2] - a[2] * b[1],
+                a[2] * b[0] - a[0] * b[2],
+                a[0] * b[1] - a[1] * b[0],
+            ]
+        };
+        let c = [cross(e[1], e[2]), cross(e[2], e[0]), cross(e[0], e[1])];
+        let det = e[0][0] * c[0][0] + e[0][1] * c[0][1] + e[0][2] * c[0][2];
+
+        // the gradient g solves e[m] · g = u[m + 1] - u[0], and the inverse of the matrix with
+        // rows e[m] has columns c[m] / det
+        let mut gradient = [[0.; 4]; 3];
+        for (d, g) in gradient.iter_mut().enumerate() {
+            for m in 0..3 {
+                g[m + 1] = (c[m][d] / det) as f32;
+                g[0] -= g[m + 1];
+            }
+        }
+
+        Tetrahedron {
+            corners,
+            gradient,
+            positive: det > 0.,
+        }
+    }
+
+    /// The gradient of the linear function with values `u` at the corners.
+    pub fn gradient<D>(&self, u: [D; 4]) -> [D; 3]
+    where
+        D: From<f32> + Copy + std::ops::Add<D, Output = D> + std::ops::Mul<D, Output = D>,
+    {
+        self.gradient.map(|g| {
+            let mut r = D::from(0.);
+            for m in 0..4 {
+                if g[m] != 0. {
+                    r = r + D::from(g[m]) * u[m];
+                }
+            }
+            r
+        })
+    }
+
+    /// Orients a face emitted by `tetrahedron` so that its normal points towards the values
+    /// above the level.
+    pub fn orient(&self, f: [u32; 3]) -> [u32; 3] {
+        if self.positive {
+            f
+        } else {
+            [f[0], f[2], f[1]]
+        }
+    }
+}
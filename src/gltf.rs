@@ -0,0 +1,356 @@
+//! glTF 2.0 binary (`.glb`) output.
+
+use crate::attribute::PlyProperty;
+use crate::mesh::Mesh;
+use std::io::{self, Write};
+
+/// How several meshes are arranged in a glTF scene.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GltfLayout {
+    /// Every mesh is a separate glTF mesh with its own node.
+    Nodes,
+    /// The meshes are primitives of a single glTF mesh with a single node.
+    Primitives,
+}
+
+/// Options for `write_glb`.
+#[derive(Clone, Copy, Debug)]
+pub struct GltfOptions<'a> {
+    pub layout: GltfLayout,
+    /// The name of the vertex attributes of the data.
+    ///
+    /// The data is split into scalar properties as described by `PlyProperty`, and each of them
+    /// is written as a custom attribute with the uppercase property name prefixed by `_`, for
+    /// example `_DATA` or `_DATA_0`, `_DATA_1`, ... The values are converted to `f32`.
+    pub data_name: &'a str,
+}
+
+impl<'a> Default for GltfOptions<'a> {
+    fn default() -> Self {
+        GltfOptions {
+            layout: GltfLayout::Nodes,
+            data_name: "data",
+        }
+    }
+}
+
+// constants of the glTF specification
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const TRIANGLES: u32 = 4;
+
+/// Writes named meshes, for example the isosurfaces at several levels, as a glTF 2.0 scene in
+/// the binary `.glb` container.
+///
+/// Every mesh has the attributes `POSITION` and `NORMAL`, with the normals scaled to unit
+/// length, and the data attributes as configured by `options`. A normal that is zero or not
+/// finite is replaced by the sum of the normals of the adjacent faces weighted by their areas,
+/// or by `[0, 0, 1]` if that is zero as well. Meshes without faces are written as nodes without
+/// a mesh, or left out of the mesh with `GltfLayout::Primitives`.
+///
+/// The bounds of the positions only take finite coordinates into account, so that the JSON
+/// stays valid.
+pub fn write_glb<W, D, T>(
+    writer: W,
+    meshes: &[(&str, &Mesh<D, T>)],
+    options: &GltfOptions,
+) -> io::Result<()>
+where
+    W: Write,
+    D: Copy + Into<f64>,
+    T: PlyProperty,
+{
+    let mut props = Vec::new();
+    T::describe(options.data_name, &mut props);
+    let attr_names: Vec<String> = props
+        .iter()
+        .map(|(name, _)| format!("_{}", name.to_uppercase()))
+        .collect();
+
+    let mut buffer = Buffer::default();
+
+    // the JSON of the primitive of every nonempty mesh
+    let primitives: Vec<Option<String>> = meshes
+        .iter()
+        .map(|&(_, mesh)| {
+            if mesh.faces.is_empty() {
+                return None;
+            }
+
+            let verts: Vec<[f32; 3]> = mesh
+                .verts
+                .iter()
+                .map(|v| v.map(|x| x.into() as f32))
+                .collect();
+            let normals = unit_normals(mesh);
+
+            let mut attributes = vec![
+                ("POSITION".to_string(), buffer.vec3(&verts, true)),
+                ("NORMAL".to_string(), buffer.vec3(&normals, false)),
+            ];
+
+            let mut values = Vec::with_capacity(props.len());
+            let mut columns = vec![Vec::with_capacity(mesh.data.len()); props.len()];
+            for d in &mesh.data {
+                values.clear();
+                d.values(&mut values);
+                for (c, v) in columns.iter_mut().zip(&values) {
+                    c.push(v.to_f64() as f32);
+                }
+            }
+            for (name, c) in attr_names.iter().zip(&columns) {
+                attributes.push((name.clone(), buffer.scalars(c)));
+            }
+
+            let indices = buffer.indices(&mesh.faces);
+
+            let attributes: Vec<String> = attributes
+                .iter()
+                .map(|(name, a)| format!("{}:{}", json_string(name), a))
+                .collect();
+            Some(format!(
+                r#"{{"attributes":{{{}}},"indices":{},"mode":{}}}"#,
+                attributes.join(","),
+                indices,
+                TRIANGLES
+            ))
+        })
+        .collect();
+
+    let mut json_meshes = Vec::new();
+    let mut json_nodes = Vec::new();
+    match options.layout {
+        GltfLayout::Nodes => {
+            for (&(name, _), primitive) in meshes.iter().zip(&primitives) {
+                let name = json_string(name);
+                match primitive {
+                    Some(p) => {
+                        json_nodes.push(format!(
+                            r#"{{"name":{},"mesh":{}}}"#,
+                            name,
+                            json_meshes.len()
+                        ));
+                        json_meshes.push(format!(r#"{{"name":{},"primitives":[{}]}}"#, name, p));
+                    }
+                    None => json_nodes.push(format!(r#"{{"name":{}}}"#, name)),
+                }
+            }
+        }
+        GltfLayout::Primitives => {
+            let ps: Vec<&str> = primitives.iter().flatten().map(|p| p.as_str()).collect();
+            if ps.is_empty() {
+                json_nodes.push("{}".to_string());
+            } else {
+                json_nodes.push(r#"{"mesh":0}"#.to_string());
+                json_meshes.push(format!(r#"{{"primitives":[{}]}}"#, ps.join(",")));
+            }
+        }
+    }
+
+    let node_indices: Vec<String> = (0..json_nodes.len()).map(|i| i.to_string()).collect();
+    let mut json = r#"{"asset":{"version":"2.0","generator":"isosurface"},"scene":0"#.to_string();
+    json += &format!(
+        r#","scenes":[{{"nodes":[{}]}}],"nodes":[{}]"#,
+        node_indices.join(","),
+        json_nodes.join(","),
+    );
+    if !json_meshes.is_empty() {
+        json += &format!(
+            r#","meshes":[{}],"accessors":[{}],"bufferViews":[{}],"buffers":[{{"byteLength":{}}}]"#,
+            json_meshes.join(","),
+            buffer.accessors.join(","),
+            buffer.views.join(","),
+            buffer.data.len(),
+        );
+    }
+    json += "}";
+
+    write_container(writer, json.into_bytes(), buffer.data)
+}
+
+/// The normals of the mesh scaled to unit length, with the normals that cannot be scaled
+/// replaced as described in `write_glb`.
+fn unit_normals<D, T>(mesh: &Mesh<D, T>) -> Vec<[f32; 3]>
+where
+    D: Copy + Into<f64>,
+{
+    let unit = |n: [f64; 3]| {
+        let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+        (len > 0. && len.is_finite()).then(|| n.map(|x| (x / len) as f32))
+    };
+
+    let mut normals: Vec<Option<[f32; 3]>> = mesh
+        .normals
+        .iter()
+        .map(|n| unit(n.map(|x| x.into())))
+        .collect();
+    if normals.iter().all(|n| n.is_some()) {
+        return normals.into_iter().flatten().collect();
+    }
+
+    // the sums of the normals of the faces, with the length twice their area
+    let mut sums = vec![[0.; 3]; normals.len()];
+    for f in &mesh.faces {
+        let [a, b, c] = f.map(|v| mesh.verts[v as usize].map(|x| x.into()));
+        let (ab, ac) = (
+            [0, 1, 2].map(|d| b[d] - a[d]),
+            [0, 1, 2].map(|d| c[d] - a[d]),
+        );
+        let n = [
+            ab[1] * ac[2] - ab[2] * ac[1],
+            ab[2] * ac[0] - ab[0] * ac[2],
+            ab[0] * ac[1] - ab[1] * ac[0],
+        ];
+        for &v in f {
+            let v = v as usize;
+            if normals[v].is_none() {
+                sums[v] = [0, 1, 2].map(|d| sums[v][d] + n[d]);
+            }
+        }
+    }
+    for (n, sum) in normals.iter_mut().zip(sums) {
+        if n.is_none() {
+            *n = Some(unit(sum).unwrap_or([0., 0., 1.]));
+        }
+    }
+    normals.into_iter().flatten().collect()
+}
+
+/// The binary buffer with its views and accessors.
+#[derive(Default)]
+struct Buffer {
+    data: Vec<u8>,
+    views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl Buffer {
+    /// Adds a view of `bytes` and an accessor, returning the index of the accessor.
+    fn add(&mut self, bytes: &[u8], target: u32, accessor: String) -> usize {
+        self.views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+            self.data.len(),
+            bytes.len(),
+            target
+        ));
+        // all components are 4 bytes long, so the views stay aligned
+        self.data.extend_from_slice(bytes);
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn vec3(&mut self, values: &[[f32; 3]], min_max: bool) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let mut accessor = format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"VEC3""#,
+            self.views.len(),
+            FLOAT,
+            values.len()
+        );
+        if min_max {
+            let (mut min, mut max) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+            for v in values {
+                for d in 0..3 {
+                    if v[d].is_finite() {
+                        min[d] = min[d].min(v[d]);
+                        max[d] = max[d].max(v[d]);
+                    }
+                }
+            }
+            // no finite coordinates at all
+            for d in 0..3 {
+                if min[d] > max[d] {
+                    (min[d], max[d]) = (0., 0.);
+                }
+            }
+            accessor += &format!(r#","min":{},"max":{}"#, json_vec3(min), json_vec3(max));
+        }
+        accessor += "}";
+        self.add(&bytes, ARRAY_BUFFER, accessor)
+    }
+
+    fn scalars(&mut self, values: &[f32]) -> usize {
+        let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let accessor = format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"SCALAR"}}"#,
+            self.views.len(),
+            FLOAT,
+            values.len()
+        );
+        self.add(&bytes, ARRAY_BUFFER, accessor)
+    }
+
+    fn indices(&mut self, faces: &[[u32; 3]]) -> usize {
+        let bytes: Vec<u8> = faces
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let accessor = format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"SCALAR"}}"#,
+            self.views.len(),
+            UNSIGNED_INT,
+            faces.len() * 3
+        );
+        self.add(&bytes, ELEMENT_ARRAY_BUFFER, accessor)
+    }
+}
+
+/// Writes the GLB header, the JSON chunk and the binary chunk.
+fn write_container<W: Write>(writer: W, mut json: Vec<u8>, mut bin: Vec<u8>) -> io::Result<()> {
+    // chunks are 4-byte aligned, JSON is padded with spaces and binary data with zeros
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    while !bin.len().is_multiple_of(4) {
+        bin.push(0);
+    }
+
+    let mut len = 12 + 8 + json.len();
+    if !bin.is_empty() {
+        len += 8 + bin.len();
+    }
+
+    let mut w = io::BufWriter::new(writer);
+    w.write_all(b"glTF")?;
+    w.write_all(&2u32.to_le_bytes())?;
+    w.write_all(&(len as u32).to_le_bytes())?;
+
+    w.write_all(&(json.len() as u32).to_le_bytes())?;
+    w.write_all(b"JSON")?;
+    w.write_all(&json)?;
+
+    if !bin.is_empty() {
+        w.write_all(&(bin.len() as u32).to_le_bytes())?;
+        w.write_all(b"BIN\0")?;
+        w.write_all(&bin)?;
+    }
+
+    w.flush()
+}
+
+fn json_vec3(v: [f32; 3]) -> String {
+    format!("[{},{},{}]", v[0], v[1], v[2])
+}
+
+/// A JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut r = String::with_capacity(s.len() + 2);
+    r.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => r.push_str("\\\""),
+            '\\' => r.push_str("\\\\"),
+            c if (c as u32) < 0x20 => r.push_str(&format!("\\u{:04x}", c as u32)),
+            c => r.push(c),
+        }
+    }
+    r.push('"');
+    r
+}
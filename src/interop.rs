@@ -0,0 +1,136 @@
+//! Interoperability with the vector types of other crates.
+//!
+//! With the `nalgebra`, `glam` and `mint` features, their vector and point types implement
+//! `Interpolate`, so they can be used as data attached to the grid nodes. `VectorMesh` collects
+//! the output of the drivers directly in these types.
+
+#[cfg(any(feature = "nalgebra", feature = "glam", feature = "mint"))]
+use crate::interpolate::Interpolate;
+use crate::sink::MeshSink;
+
+/// A triangular mesh with the vertices of type `P` and the normals of type `N`, such as
+/// `nalgebra::Point3`, `glam::Vec3` or `mint::Point3`.
+///
+/// As a `MeshSink`, every position and normal is converted as the driver emits it, so the mesh is
+/// never stored as arrays first. The fields are as in `Mesh`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorMesh<P, N = P, T = ()> {
+    pub verts: Vec<P>,
+    pub faces: Vec<[u32; 3]>,
+    pub normals: Vec<N>,
+    pub data: Vec<T>,
+}
+
+impl<P, N, T> VectorMesh<P, N, T> {
+    pub fn new() -> VectorMesh<P, N, T> {
+        VectorMesh {
+            verts: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+}
+
+impl<P, N, T> Default for VectorMesh<P, N, T> {
+    fn default() -> Self {
+        VectorMesh::new()
+    }
+}
+
+impl<D, T, P, N> MeshSink<D, T> for VectorMesh<P, N, T>
+where
+    P: From<[D; 3]>,
+    N: From<[D; 3]>,
+{
+    fn vertex(&mut self, pos: [D; 3], normal: [D; 3], data: T) -> u32 {
+        self.verts.push(pos.into());
+        self.normals.push(normal.into());
+        self.data.push(data);
+        (self.verts.len() - 1) as u32
+    }
+
+    fn face(&mut self, face: [u32; 3]) {
+        self.faces.push(face);
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T, const N: usize> Interpolate<T> for nalgebra::SVector<T, N>
+where
+    T: nalgebra::Scalar + Interpolate<T> + Copy,
+{
+    fn interpolate(&self, other: &Self, a: T, b: T) -> Self {
+        self.zip_map(other, |x, y| x.interpolate(&y, a, b))
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T, const N: usize> Interpolate<T> for nalgebra::Point<T, N>
+where
+    T: nalgebra::Scalar + Interpolate<T> + Copy,
+{
+    fn interpolate(&self, other: &Self, a: T, b: T) -> Self {
+        self.coords.interpolate(&other.coords, a, b).into()
+    }
+}
+
+#[cfg(feature = "glam")]
+macro_rules! impl_interpolate_glam {
+    ($t:ty, $s:ty) => {
+        impl Interpolate<$s> for $t {
+            #[inline]
+            fn interpolate(&self, other: &Self, a: $s, b: $s) -> Self {
+                let x = a / (a - b);
+                (1. - x) * *self + x * *other
+            }
+        }
+    };
+}
+
+#[cfg(feature = "glam")]
+impl_interpolate_glam!(glam::Vec2, f32);
+#[cfg(feature = "glam")]
+impl_interpolate_glam!(glam::Vec3, f32);
+#[cfg(feature = "glam")]
+impl_interpolate_glam!(glam::Vec3A, f32);
+#[cfg(feature = "glam")]
+impl_interpolate_glam!(glam::Vec4, f32);
+#[cfg(feature = "glam")]
+impl_interpolate_glam!(glam::DVec2, f64);
+#[cfg(feature = "glam")]
+impl_interpolate_glam!(glam::DVec3, f64);
+#[cfg(feature = "glam")]
+impl_interpolate_glam!(glam::DVec4, f64);
+
+#[cfg(feature = "mint")]
+macro_rules! impl_interpolate_mint {
+    ($t:ident { $($c:ident),* }) => {
+        impl<T, U> Interpolate<T> for mint::$t<U>
+        where
+            T: Copy,
+            U: Interpolate<T>,
+        {
+            fn interpolate(&self, other: &Self, a: T, b: T) -> Self {
+                mint::$t {
+                    $($c: self.$c.interpolate(&other.$c, a, b),)*
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "mint")]
+impl_interpolate_mint!(Vector2 { x, y });
+#[cfg(feature = "mint")]
+impl_interpolate_mint!(Vector3 { x, y, z });
+#[cfg(feature = "mint")]
+impl_interpolate_mint!(Vector4 { x, y, z, w });
+#[cfg(feature = "mint")]
+impl_interpolate_mint!(Point2 { x, y });
+#[cfg(feature = "mint")]
+impl_interpolate_mint!(Point3 { x, y, z });
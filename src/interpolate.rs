@@ -36,9 +36,7 @@ where
 }
 
 impl<T> Interpolate<T> for () {
-    fn interpolate(&self, _other: &Self, _a: T, _b: T) -> Self {
-        ()
-    }
+    fn interpolate(&self, _other: &Self, _a: T, _b: T) -> Self {}
 }
 
 impl<T, U, V> Interpolate<T> for (U, V)
@@ -1,14 +1,16 @@
 use crate::interpolate::Interpolate;
+use crate::scalar::Scalar;
+use crate::view::StridedView;
 use std::collections::HashMap;
 
 #[derive(Clone, Debug)]
-pub struct Isoline {
-    verts: Vec<[f64; 2]>,
+pub struct Isoline<D = f64> {
+    verts: Vec<[D; 2]>,
     components: Vec<usize>,
 }
 
-impl Isoline {
-    pub fn components(&self) -> Components {
+impl<D> Isoline<D> {
+    pub fn components(&self) -> Components<'_, D> {
         Components {
             isoline: self,
             component: 0,
@@ -16,13 +18,13 @@ impl Isoline {
     }
 }
 
-pub struct Components<'a> {
-    isoline: &'a Isoline,
+pub struct Components<'a, D = f64> {
+    isoline: &'a Isoline<D>,
     component: usize,
 }
 
-impl<'a> Iterator for Components<'a> {
-    type Item = &'a [[f64; 2]];
+impl<'a, D> Iterator for Components<'a, D> {
+    type Item = &'a [[D; 2]];
 
     fn next(&mut self) -> Option<Self::Item> {
         let comps = &self.isoline.components;
@@ -35,12 +37,107 @@ impl<'a> Iterator for Components<'a> {
     }
 }
 
-impl<'a> ExactSizeIterator for Components<'a> {
+impl<'a, D> ExactSizeIterator for Components<'a, D> {
     fn len(&self) -> usize {
         self.isoline.components.len() - self.component - 1
     }
 }
 
+/// Options for the marching triangles drivers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IsolineOptions<'a> {
+    /// Periodicity of the grid along the first and the second axis.
+    ///
+    /// Along a periodic axis of dimension `n`, the node `n` is identified with the node `0`, and
+    /// the components of the isoline are traced across the boundary of the grid.
+    pub periodic: [bool; 2],
+    /// Keep the coordinates along periodic axes unwrapped.
+    ///
+    /// By default, the coordinates along a periodic axis of dimension `n` are kept in the
+    /// fundamental domain `[0, n)` and jump where a component crosses the boundary of the grid.
+    /// If set, the coordinates along a component change continuously, so they might leave the
+    /// fundamental domain, and a closed curve winding around the domain ends at a shifted copy of
+    /// its first vertex.
+    pub unwrap: bool,
+    /// Valid nodes of the grid, in _row-major order_.
+    ///
+    /// Triangles with an invalid node are skipped, so the isoline ends at the boundary of the
+    /// valid region.
+    pub mask: Option<&'a [bool]>,
+    /// Treat nodes where `u` is NaN as invalid.
+    ///
+    /// Otherwise NaN values compare as below any level.
+    pub skip_nan: bool,
+}
+
+/// The grid extended by one node along the periodic axes.
+///
+/// The squares of the grid are indexed by their corner with the largest indices. They are
+/// split into two triangles with six sides (the diagonal is duplicated) connecting the corners
+/// `0, ..., 3`, where corner `c` has index `s - vs[3 - c]` in the square with index `s`.
+struct Grid {
+    dim: (usize, usize),
+    ext: (usize, usize),
+    vs: [usize; 4],
+}
+
+/// The sides of the two triangles within a square.
+const SIDES: [[usize; 2]; 6] = [[0, 2], [2, 3], [0, 3], [0, 3], [0, 1], [1, 3]];
+
+impl Grid {
+    fn new(dim: (usize, usize), periodic: [bool; 2]) -> Grid {
+        let ext = (dim.0 + periodic[0] as usize, dim.1 + periodic[1] as usize);
+        let (si, sj) = (ext.1, 1);
+        Grid {
+            dim,
+            ext,
+            vs: [0, si, sj, si + sj],
+        }
+    }
+
+    /// Index in the grid of a node.
+    fn node(&self, s: usize) -> [usize; 2] {
+        [s / self.ext.1 % self.dim.0, s % self.ext.1 % self.dim.1]
+    }
+
+    /// The nodes at the ends of a side of a triangle.
+    fn side(&self, (s, ei): (usize, usize)) -> [usize; 2] {
+        SIDES[ei].map(|c| s - self.vs[3 - c])
+    }
+
+    /// Coordinates of the point with parameters `x` and `y` of `Interpolate` on a side.
+    ///
+    /// With `options.unwrap`, the coordinates are shifted by `shift`, otherwise they are moved
+    /// into the fundamental domain.
+    fn coord<D: Scalar>(
+        &self,
+        side: (usize, usize),
+        x: D,
+        y: D,
+        shift: [D; 2],
+        options: &IsolineOptions,
+    ) -> [D; 2] {
+        let [s1, s2] = self.side(side);
+        let coord = |s: usize| {
+            [
+                D::from((s / self.ext.1) as f32),
+                D::from((s % self.ext.1) as f32),
+            ]
+        };
+
+        let mut p = coord(s1).interpolate(&coord(s2), x, y);
+        for (d, n) in [self.dim.0, self.dim.1].into_iter().enumerate() {
+            let n = D::from(n as f32);
+            if options.unwrap {
+                p[d] = p[d] + shift[d];
+            } else if options.periodic[d] && p[d] >= n {
+                p[d] = p[d] - n;
+            }
+        }
+        p
+    }
+}
+
 /// Finds the isoline (level set) at `level` of a function given by its values `u` on a regular
 /// grid using a marching triangles algorithm.
 ///
@@ -50,92 +147,70 @@ impl<'a> ExactSizeIterator for Components<'a> {
 /// on each triangle.
 ///
 /// Returns a `Vec` of all connected components of the isoline.
-pub fn marching_triangles(u: &[f64], dim: (usize, usize), level: f64) -> Isoline {
+pub fn marching_triangles<D: Scalar>(u: &[D], dim: (usize, usize), level: D) -> Isoline<D> {
+    marching_triangles_with_options(u, dim, level, &IsolineOptions::default())
+}
+
+/// As `marching_triangles`, with the periodicity and valid nodes given by `options`.
+pub fn marching_triangles_with_options<D: Scalar>(
+    u: &[D],
+    dim: (usize, usize),
+    level: D,
+    options: &IsolineOptions,
+) -> Isoline<D> {
     let (ni, nj) = dim;
-    assert_eq!(ni * nj, u.len());
+    marching_triangles_strided(StridedView::row_major(u, [ni, nj]), level, options)
+}
 
-    let mut verts: Vec<[f64; 2]> = Vec::new();
+/// As `marching_triangles_with_options`, with `u` given by a strided view.
+pub fn marching_triangles_strided<D: Scalar>(
+    u: StridedView<D, 2>,
+    level: D,
+    options: &IsolineOptions,
+) -> Isoline<D> {
+    let [ni, nj] = u.shape();
+    let dim = (ni, nj);
+    let grid = Grid::new(dim, options.periodic);
+    let (si, sj) = (grid.ext.1, 1);
+
+    let mut verts: Vec<[D; 2]> = Vec::new();
     let mut components: Vec<usize> = Vec::new();
 
     // first find all the edges connecting the sides of the triangles
     let mut edges: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
 
-    let (si, sj) = (nj, 1);
-
-    // vertices 3, 2, 1, 0
-    // index is (s - vs[i])
-    let vs = [0, si, sj, si + sj];
-    // six sides of the two triangles within a square (diagonal is duplicated)
-    // connecting vertices 0,...,3
-    let es = [[0, 2], [2, 3], [0, 3], [0, 3], [0, 1], [1, 3]];
-
-    let bit = |k: u32, bit: u32| (k & (1 << bit)) >> bit;
-
-    // a map between a bit mask and the list between connected triangle sides
-    let table = {
-        let mut table = [[6; 4]; 16];
-
-        for i in 0..16 {
-            let mut c = 0;
-            for (ei, edge) in es.iter().enumerate() {
-                if bit(i, edge[0] as u32) != bit(i, edge[1] as u32) {
-                    table[i as usize][c] = ei;
-                    c += 1;
-                }
-            }
-        }
-
-        table
-    };
-
-    // generate the map of edges connected by the level set
-    for i in 1..ni {
-        for j in 1..nj {
-            let s = i * si + j * sj;
-
-            let mask = {
-                let mut mask = 0;
-
-                for &sd in vs.iter() {
-                    mask = (mask << 1) + (if u[s - sd] >= level { 1 } else { 0 });
-                }
-
-                mask
-            };
-
-            for edge in table[mask].chunks(2).take_while(|c| c[0] < 6) {
-                let a = (s, edge[0]);
-                let b = (s, edge[1]);
+    marching_triangles_emit_connected_edges(&u, level, options, |a, b| {
+        edges.insert(a, b);
+        edges.insert(b, a);
+    });
 
-                edges.insert(a, b);
-                edges.insert(b, a);
-            }
+    // each line is a side of two triangles, this is a mapping between them; also returns the
+    // shift of the coordinates when crossing the boundary of a periodic grid
+    let zero = D::from(0.);
+    let dual = |(s, ei): (usize, usize)| {
+        let (i, j) = (s / si, s % si);
+        let (n, m) = (D::from(ni as f32), D::from(nj as f32));
+        let periodic = options.periodic;
+        match ei {
+            0 if periodic[0] && i == 1 => ((s + (ni - 1) * si, 5), [zero - n, zero]),
+            5 if periodic[0] && i == ni => ((s - (ni - 1) * si, 0), [n, zero]),
+            1 if periodic[1] && j == nj => ((s - (nj - 1) * sj, 4), [zero, m]),
+            4 if periodic[1] && j == 1 => ((s + (nj - 1) * sj, 1), [zero, zero - m]),
+            0 => ((s - si, 5), [zero; 2]),
+            1 => ((s + sj, 4), [zero; 2]),
+            2 => ((s, 3), [zero; 2]),
+            3 => ((s, 2), [zero; 2]),
+            4 => ((s - sj, 1), [zero; 2]),
+            5 => ((s + si, 0), [zero; 2]),
+            _ => unreachable!(),
         }
-    }
-
-    // each line is a side of two triangles, this is a mapping between them
-    let dual = |(s, ei)| match ei {
-        0 => (s - si, 5),
-        1 => (s + sj, 4),
-        2 => (s, 3),
-        3 => (s, 2),
-        4 => (s - sj, 1),
-        5 => (s + si, 0),
-        _ => unreachable!(),
     };
 
     // finds the intersection between the isoline and an edge
-    let to_coord = |(s, ei): (usize, usize)| {
-        let s1 = s - vs[3 - es[ei][0]];
-        let s2 = s - vs[3 - es[ei][1]];
-
-        let coord = |s| {
-            let div = s as f64 * (1. / nj as f64);
-
-            [div.trunc(), div.fract() * nj as f64]
-        };
-
-        coord(s1).interpolate(&coord(s2), u[s1] - level, u[s2] - level)
+    let to_coord = |side: (usize, usize), shift: [D; 2]| {
+        let [s1, s2] = grid.side(side);
+        let (x, y) = (*u.get(grid.node(s1)) - level, *u.get(grid.node(s2)) - level);
+        grid.coord(side, x, y, shift, options)
     };
 
     // find connected components in the edge graph
@@ -145,62 +220,72 @@ pub fn marching_triangles(u: &[f64], dim: (usize, usize), level: f64) -> Isoline
         let comp_begin = verts.len();
         components.push(comp_begin);
 
-        verts.push(to_coord(start));
+        verts.push(to_coord(start, [zero; 2]));
 
-        let trace = |start, edges: &mut HashMap<_, _>, verts: &mut Vec<_>| {
+        let trace = |start, shift: [D; 2], edges: &mut HashMap<_, _>, verts: &mut Vec<_>| {
             let mut next = start;
+            let mut shift = shift;
 
             while let Some(other) = edges.remove(&next) {
                 edges.remove(&other);
 
-                next = dual(other);
+                let (side, d) = dual(other);
+                next = side;
+                shift = [shift[0] + d[0], shift[1] + d[1]];
 
-                verts.push(to_coord(next));
+                verts.push(to_coord(next, shift));
             }
         };
 
-        trace(start, &mut edges, &mut verts);
+        trace(start, [zero; 2], &mut edges, &mut verts);
 
-        let start = dual(start);
+        let (start, shift) = dual(start);
 
         // if the component is not a closed curve, we might have to walk in the other direction
         // from the starting side to find the full component
         if edges.contains_key(&start) {
             verts[comp_begin..].reverse();
 
-            trace(start, &mut edges, &mut verts);
+            trace(start, shift, &mut edges, &mut verts);
         }
     }
 
     components.push(verts.len());
 
-    Isoline {
-        verts: verts,
-        components: components,
-    }
+    Isoline { verts, components }
 }
 
 /// Emits indices of edges of the triangular mesh connect by the `level` level set curve of
 /// function given by values `u` at the nodes.
-pub fn marching_triangles_emit_connected_edges<F>(
-    u: &[f64],
-    dim: (usize, usize),
-    level: f64,
+///
+/// A side of a triangle is given by the index of its square in the grid extended by one node
+/// along the periodic axes and the index of the side in the square.
+pub fn marching_triangles_emit_connected_edges<D, F>(
+    u: &StridedView<D, 2>,
+    level: D,
+    options: &IsolineOptions,
     mut emit: F,
 ) where
+    D: Scalar,
     F: FnMut((usize, usize), (usize, usize)),
 {
-    let (ni, nj) = dim;
-    assert_eq!(ni * nj, u.len());
+    let [ni, nj] = u.shape();
+    let dim = (ni, nj);
+    if let Some(mask) = options.mask {
+        assert_eq!(ni * nj, mask.len());
+    }
 
-    let (si, sj) = (nj, 1);
+    let grid = Grid::new(dim, options.periodic);
+    let (ei, ej) = grid.ext;
+    let (si, sj) = (ej, 1);
+    let vs = grid.vs;
 
-    // vertices 3, 2, 1, 0
-    // index is (s - vs[i])
-    let vs = [0, si, sj, si + sj];
-    // six sides of the two triangles within a square (diagonal is duplicated)
-    // connecting vertices 0,...,3
-    let es = [[0, 2], [2, 3], [0, 3], [0, 3], [0, 1], [1, 3]];
+    // NaN is the only value not comparable to itself
+    let valid = |[i, j]: [usize; 2]| {
+        let u = u.get([i, j]);
+        options.mask.is_none_or(|mask| mask[i * nj + j])
+            && !(options.skip_nan && u.partial_cmp(u).is_none())
+    };
 
     let bit = |k: u32, bit: u32| (k & (1 << bit)) >> bit;
 
@@ -210,7 +295,7 @@ pub fn marching_triangles_emit_connected_edges<F>(
 
         for i in 0..16 {
             let mut c = 0;
-            for (ei, edge) in es.iter().enumerate() {
+            for (ei, edge) in SIDES.iter().enumerate() {
                 if bit(i, edge[0] as u32) != bit(i, edge[1] as u32) {
                     table[i as usize][c] = ei;
                     c += 1;
@@ -222,21 +307,31 @@ pub fn marching_triangles_emit_connected_edges<F>(
     };
 
     // emit indices of connected edges
-    for i in 1..ni {
-        for j in 1..nj {
+    for i in 1..ei {
+        for j in 1..ej {
             let s = i * si + j * sj;
 
+            // corners 3, 2, 1, 0
+            let nodes = vs.map(|sd| grid.node(s - sd));
+
             let mask = {
                 let mut mask = 0;
 
-                for &sd in vs.iter() {
-                    mask = (mask << 1) + (if u[s - sd] >= level { 1 } else { 0 });
+                for &n in nodes.iter() {
+                    mask = (mask << 1) + (if *u.get(n) >= level { 1 } else { 0 });
                 }
 
                 mask
             };
 
             for edge in table[mask].chunks(2).take_while(|c| c[0] < 6) {
+                // the triangle of the sides 0, 1, 2 has corners 0, 2, 3, the other one has
+                // corners 0, 1, 3
+                let corners = if edge[0] < 3 { [0, 2, 3] } else { [0, 1, 3] };
+                if !corners.iter().all(|&c| valid(nodes[3 - c])) {
+                    continue;
+                }
+
                 let a = (s, edge[0]);
                 let b = (s, edge[1]);
 
@@ -253,44 +348,74 @@ pub fn marching_triangles_emit_connected_edges<F>(
 ///
 /// The coordinate system is chosen so that the node (i, j) with index i * dim.1 + j has coordinate
 /// (i, j).
-pub fn marching_triangles_with_data_emit<F, D>(
-    u: &[f64],
-    data: &[D],
+pub fn marching_triangles_with_data_emit<F, D, T>(
+    u: &[D],
+    data: &[T],
     dim: (usize, usize),
-    level: f64,
-    mut emit: F,
+    level: D,
+    emit: F,
+) where
+    F: FnMut([[D; 2]; 2], [T; 2]),
+    D: Scalar,
+    T: Interpolate<D>,
+{
+    marching_triangles_with_options_emit(u, data, dim, level, &IsolineOptions::default(), emit);
+}
+
+/// As `marching_triangles_with_data_emit`, with the periodicity and valid nodes given by
+/// `options`.
+///
+/// With `options.unwrap`, both endpoints of a segment have coordinates within the same square
+/// of the grid.
+pub fn marching_triangles_with_options_emit<F, D, T>(
+    u: &[D],
+    data: &[T],
+    dim: (usize, usize),
+    level: D,
+    options: &IsolineOptions,
+    emit: F,
 ) where
-    F: FnMut([[f64; 2]; 2], [D; 2]),
-    D: Interpolate<f64>,
+    F: FnMut([[D; 2]; 2], [T; 2]),
+    D: Scalar,
+    T: Interpolate<D>,
 {
     let (ni, nj) = dim;
-    assert_eq!(ni * nj, u.len());
-    assert_eq!(ni * nj, data.len());
-    let (si, sj) = (nj, 1);
-    let vs = [0, si, sj, si + sj];
-    // six sides of the two triangles within a square (diagonal is duplicated)
-    // connecting vertices 0,...,3
-    let es = [[0, 2], [2, 3], [0, 3], [0, 3], [0, 1], [1, 3]];
-    // finds the intersection between the level set and an edge
-    let to_coord_with_data = |(s, ei): (usize, usize)| {
-        let s1 = s - vs[3 - es[ei][0]];
-        let s2 = s - vs[3 - es[ei][1]];
+    let u = StridedView::row_major(u, [ni, nj]);
+    let data = StridedView::row_major(data, [ni, nj]);
 
-        let coord = |s| {
-            let div = s as f64 * (1. / nj as f64);
+    marching_triangles_strided_emit(u, data, level, options, emit);
+}
 
-            [div.trunc(), div.fract() * nj as f64]
-        };
+/// As `marching_triangles_with_options_emit`, with `u` and `data` given by strided views of the
+/// same shape.
+pub fn marching_triangles_strided_emit<F, D, T>(
+    u: StridedView<D, 2>,
+    data: StridedView<T, 2>,
+    level: D,
+    options: &IsolineOptions,
+    mut emit: F,
+) where
+    F: FnMut([[D; 2]; 2], [T; 2]),
+    D: Scalar,
+    T: Interpolate<D>,
+{
+    assert_eq!(u.shape(), data.shape());
+    let [ni, nj] = u.shape();
+    let grid = Grid::new((ni, nj), options.periodic);
+
+    // finds the intersection between the level set and an edge
+    let to_coord_with_data = |side: (usize, usize)| {
+        let [s1, s2] = grid.side(side).map(|s| grid.node(s));
 
-        let x = u[s1] - level;
-        let y = u[s2] - level;
+        let x = *u.get(s1) - level;
+        let y = *u.get(s2) - level;
         (
-            coord(s1).interpolate(&coord(s2), x, y),
-            data[s1].interpolate(&data[s2], x, y),
+            grid.coord(side, x, y, [D::from(0.); 2], options),
+            data.get(s1).interpolate(data.get(s2), x, y),
         )
     };
 
-    marching_triangles_emit_connected_edges(u, dim, level, |a, b| {
+    marching_triangles_emit_connected_edges(&u, level, options, |a, b| {
         let (c0, d0) = to_coord_with_data(a);
         let (c1, d1) = to_coord_with_data(b);
         emit([c0, c1], [d0, d1]);
@@ -1,19 +1,59 @@
+use crate::decomposition::{Decomposition, Tetrahedron};
 use crate::interpolate::Interpolate;
+use crate::mesh::Mesh;
+use crate::scalar::Scalar;
+use crate::sink::MeshSink;
+use crate::snap;
+use crate::view::StridedView;
+use std::collections::HashMap;
 
-pub trait Nudge {
-    fn nudge(self) -> Self;
-}
+/// A vertex of the level set within a tetrahedron, given by the indices of the tetrahedron
+/// vertices below and above the level of the edge it lies on.
+#[derive(Clone, Copy, Debug)]
+struct Cut([usize; 2]);
 
-impl Nudge for f32 {
-    fn nudge(self) -> Self {
-        self + 1e-15
+impl<D> Interpolate<D> for Cut {
+    fn interpolate(&self, other: &Self, _a: D, _b: D) -> Self {
+        Cut([self.0[0], other.0[0]])
     }
 }
 
-impl Nudge for f64 {
-    fn nudge(self) -> Self {
-        self + 1e-15
-    }
+/// Options for the marching tetrahedra drivers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Options<'a, D> {
+    /// Splitting of the grid cells into tetrahedra.
+    pub decomposition: Decomposition,
+    /// Snapping tolerance.
+    ///
+    /// A vertex whose interpolation parameter along its edge is within the tolerance of 0 or 1
+    /// is moved to the grid node at that end of the edge and takes the data of the node. Faces
+    /// that collapse are removed, and faces left flat, with a quality of at most 1e-4 as
+    /// measured by `validate_mesh`, are replaced by edge flips. Only used by the drivers that
+    /// share vertices.
+    pub snap: Option<D>,
+    /// Periodicity of the grid along the _x_, _y_ and _z_ axis.
+    ///
+    /// Along a periodic axis of dimension `n`, the node `n` is identified with the node `0`, so
+    /// the level set is closed across the boundary of the grid. `Decomposition::Five` requires
+    /// an even dimension along periodic axes.
+    pub periodic: [bool; 3],
+    /// Keep the coordinates along periodic axes unwrapped.
+    ///
+    /// By default, vertex coordinates along a periodic axis of dimension `n` are kept in the
+    /// fundamental domain `[0, n)`, so faces crossing the boundary of the grid have vertices on
+    /// both of its sides. If set, the coordinates are in `[0, n]` and every face lies within a
+    /// single cell. The drivers that share vertices then duplicate the vertices on the boundary,
+    /// so the mesh is open there.
+    pub unwrap: bool,
+    /// Valid nodes of the grid, in _row-major order_.
+    ///
+    /// Tetrahedra with an invalid node are skipped, so the level set ends at the boundary of
+    /// the valid region.
+    pub mask: Option<&'a [bool]>,
+    /// Treat nodes where `u` is NaN as invalid.
+    ///
+    /// Otherwise NaN values compare as below any level.
+    pub skip_nan: bool,
 }
 
 /// Find the intersection of the zero level set of a linear function with a tetrahedron.
@@ -21,9 +61,22 @@ impl Nudge for f64 {
 /// The level set is determined by the function values `u` at vertices `v`. The resulting
 /// intersection emits a vertex (at most 4), and each triangle emits a face with indices into the
 /// emitted vertices.
+///
+/// Values exactly at zero are resolved by a simulation of simplicity: every vertex value is
+/// perturbed by a positive infinitesimal, so a zero value is always above the level and the
+/// level set passes through that vertex in the limit. Only the sign of the perturbation matters,
+/// so the classification of a vertex depends only on its own value, and the vertex emitted on an
+/// edge depends only on the values at its two endpoints, which makes the result consistent
+/// between tetrahedra sharing a vertex or an edge. All vertices emitted on edges ending at a
+/// zero value lie exactly at that tetrahedron vertex.
+///
+/// Each vertex is emitted as `v[i].interpolate(&v[j], u[i], u[j])` with `u[i] < 0 <= u[j]`.
+///
+/// If the vertices `v` are positively oriented, the faces are oriented so that their normals,
+/// given by the right-hand rule, point towards the vertices above zero.
 pub fn tetrahedron<D, T, FV, FF>(u: [D; 4], v: [T; 4], mut emit_vertex: FV, mut emit_face: FF)
 where
-    D: PartialOrd + Default + Nudge + Copy,
+    D: PartialOrd + Default + Copy,
     T: Interpolate<D> + Copy,
     FV: FnMut(T),
     FF: FnMut([u32; 3]),
@@ -42,17 +95,14 @@ where
 
     // START GENERATED: generated by `scripts/tetrahedron_cuts.py`
     if u0 >= zero {
-        let u0 = u0.nudge();
         if u1 >= zero {
-            let u1 = u1.nudge();
             if u2 >= zero {
-                let u2 = u2.nudge();
                 if u3 >= zero {
                 } else {
                     emit_vertex(v3.interpolate(&v0, u3, u0));
                     emit_vertex(v3.interpolate(&v1, u3, u1));
                     emit_vertex(v3.interpolate(&v2, u3, u2));
-                    emit_face([0, 1, 2]);
+                    emit_face([0, 2, 1]);
                 }
             } else {
                 if u3 >= zero {
@@ -71,19 +121,18 @@ where
             }
         } else {
             if u2 >= zero {
-                let u2 = u2.nudge();
                 if u3 >= zero {
                     emit_vertex(v1.interpolate(&v0, u1, u0));
                     emit_vertex(v1.interpolate(&v2, u1, u2));
                     emit_vertex(v1.interpolate(&v3, u1, u3));
-                    emit_face([0, 1, 2]);
+                    emit_face([0, 2, 1]);
                 } else {
                     emit_vertex(v1.interpolate(&v0, u1, u0));
                     emit_vertex(v1.interpolate(&v2, u1, u2));
                     emit_vertex(v3.interpolate(&v0, u3, u0));
                     emit_vertex(v3.interpolate(&v2, u3, u2));
-                    emit_face([0, 1, 2]);
-                    emit_face([2, 1, 3]);
+                    emit_face([0, 2, 1]);
+                    emit_face([2, 3, 1]);
                 }
             } else {
                 if u3 >= zero {
@@ -97,15 +146,13 @@ where
                     emit_vertex(v1.interpolate(&v0, u1, u0));
                     emit_vertex(v2.interpolate(&v0, u2, u0));
                     emit_vertex(v3.interpolate(&v0, u3, u0));
-                    emit_face([0, 1, 2]);
+                    emit_face([0, 2, 1]);
                 }
             }
         }
     } else {
         if u1 >= zero {
-            let u1 = u1.nudge();
             if u2 >= zero {
-                let u2 = u2.nudge();
                 if u3 >= zero {
                     emit_vertex(v0.interpolate(&v1, u0, u1));
                     emit_vertex(v0.interpolate(&v2, u0, u2));
@@ -125,8 +172,8 @@ where
                     emit_vertex(v0.interpolate(&v3, u0, u3));
                     emit_vertex(v2.interpolate(&v1, u2, u1));
                     emit_vertex(v2.interpolate(&v3, u2, u3));
-                    emit_face([0, 1, 2]);
-                    emit_face([2, 1, 3]);
+                    emit_face([0, 2, 1]);
+                    emit_face([2, 3, 1]);
                 } else {
                     emit_vertex(v0.interpolate(&v1, u0, u1));
                     emit_vertex(v2.interpolate(&v1, u2, u1));
@@ -136,7 +183,6 @@ where
             }
         } else {
             if u2 >= zero {
-                let u2 = u2.nudge();
                 if u3 >= zero {
                     emit_vertex(v0.interpolate(&v2, u0, u2));
                     emit_vertex(v0.interpolate(&v3, u0, u3));
@@ -148,7 +194,7 @@ where
                     emit_vertex(v0.interpolate(&v2, u0, u2));
                     emit_vertex(v1.interpolate(&v2, u1, u2));
                     emit_vertex(v3.interpolate(&v2, u3, u2));
-                    emit_face([0, 1, 2]);
+                    emit_face([0, 2, 1]);
                 }
             } else {
                 if u3 >= zero {
@@ -156,7 +202,6 @@ where
                     emit_vertex(v1.interpolate(&v3, u1, u3));
                     emit_vertex(v2.interpolate(&v3, u2, u3));
                     emit_face([0, 1, 2]);
-                } else {
                 }
             }
         }
@@ -169,152 +214,536 @@ where
 ///
 /// `dim` is the dimension of the array `u` assumed to be in _row-major order_ (C order).
 ///
-/// Returns vertices, faces and normals of the generated triangular mesh.
-pub fn marching_tetrahedra<D>(
+/// Returns the vertices, faces and normals of the generated triangular mesh.
+pub fn marching_tetrahedra<D>(u: &[D], dim: (usize, usize, usize), level: D) -> Mesh<D>
+where
+    D: Scalar,
+{
+    marching_tetrahedra_with_data(u, dim, level, &vec![(); u.len()])
+}
+
+/// As `marching_tetrahedra`, but also linearly interpolates the provided data for each vertex.
+pub fn marching_tetrahedra_with_data<D, T>(
     u: &[D],
     dim: (usize, usize, usize),
     level: D,
-) -> (Vec<[D; 3]>, Vec<[u32; 3]>, Vec<[D; 3]>)
+    data: &[T],
+) -> Mesh<D, T>
 where
-    D: Interpolate<D>
-        + From<f32>
-        + PartialOrd
-        + std::ops::Sub<D, Output = D>
-        + Copy
-        + Default
-        + Nudge
-        + std::ops::Add<D, Output = D>,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
 {
-    let (verts, faces, normals, _) =
-        marching_tetrahedra_with_data(u, dim, level, &vec![(); u.len()]);
+    marching_tetrahedra_with_options(u, dim, level, data, &Options::default())
+}
 
-    (verts, faces, normals)
+/// As `marching_tetrahedra_with_data`, with the cell decomposition, periodicity and valid nodes
+/// given by `options`.
+///
+/// The faces are oriented so that their normals, given by the right-hand rule, point towards
+/// the values above `level`.
+pub fn marching_tetrahedra_with_options<D, T>(
+    u: &[D],
+    dim: (usize, usize, usize),
+    level: D,
+    data: &[T],
+    options: &Options<'_, D>,
+) -> Mesh<D, T>
+where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    let (ni, nj, nk) = dim;
+    let u = StridedView::row_major(u, [ni, nj, nk]);
+    let data = StridedView::row_major(data, [ni, nj, nk]);
+
+    marching_tetrahedra_strided(u, level, data, options)
 }
 
-/// As `marching_tetrahedra`, but also linearly interpolates the provided data for each vertex.
-pub fn marching_tetrahedra_with_data<D, T>(
+/// As `marching_tetrahedra_with_options`, with `u` and `data` given by strided views of the
+/// same shape.
+pub fn marching_tetrahedra_strided<D, T>(
+    u: StridedView<D, 3>,
+    level: D,
+    data: StridedView<T, 3>,
+    options: &Options<'_, D>,
+) -> Mesh<D, T>
+where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    marching_tetrahedra_samples(u, level, data, options)
+}
+
+/// As `marching_tetrahedra_strided`, with values `u` of any type that converts into `D`, such
+/// as `u8`, `u16`, `i16` or `half::f16` into `f32` or `f64`.
+///
+/// The values are converted as they are read, so the whole volume is never converted at once.
+/// The level set is found and the vertices are interpolated in `D`.
+pub fn marching_tetrahedra_samples<S, D, T>(
+    u: StridedView<S, 3>,
+    level: D,
+    data: StridedView<T, 3>,
+    options: &Options<'_, D>,
+) -> Mesh<D, T>
+where
+    S: Into<D> + Copy,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    let mut mesh = Mesh::new();
+    marching_tetrahedra_sink(u, level, data, options, &mut mesh);
+    mesh
+}
+
+/// As `marching_tetrahedra_samples`, but the mesh is passed to `sink` instead of returned.
+///
+/// The vertices of each triangle are emitted just before it, with the gradient of `u` in the
+/// tetrahedron as their normal.
+pub fn marching_tetrahedra_sink<S, D, T, K>(
+    u: StridedView<S, 3>,
+    level: D,
+    data: StridedView<T, 3>,
+    options: &Options<'_, D>,
+    sink: &mut K,
+) where
+    S: Into<D> + Copy,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+    K: MeshSink<D, T> + ?Sized,
+{
+    assert_eq!(u.shape(), data.shape());
+    let [ni, nj, nk] = u.shape();
+    let dim = (ni, nj, nk);
+
+    let mut verts: Vec<([D; 3], T)> = Vec::with_capacity(4);
+    let mut faces: Vec<[u32; 3]> = Vec::with_capacity(2);
+
+    sink.begin();
+    for_each_tetrahedron(&u, level, options, |tet, nodes, _, vs, us| {
+        verts.clear();
+        faces.clear();
+        tetrahedron(
+            us,
+            [0, 1, 2, 3].map(|m| (vs[m], *data.get(nodes[m]))),
+            |v| verts.push(v),
+            |f| faces.push(tet.orient(f)),
+        );
+
+        if verts.is_empty() {
+            return;
+        }
+
+        let n = tet.gradient(us);
+        let mut idx = [0; 4];
+        for (&(v, d), idx) in verts.iter().zip(&mut idx) {
+            *idx = sink.vertex(wrap_position(v, dim, options), n, d);
+        }
+        for f in &faces {
+            sink.face(f.map(|v| idx[v as usize]));
+        }
+    });
+    sink.end();
+}
+
+/// As `marching_tetrahedra_with_options`, but the vertices are shared between adjacent
+/// triangles.
+///
+/// Every vertex lies on an edge of the grid and is emitted only once, so the result is a
+/// connected, crack-free 2-manifold mesh even if some values are exactly at `level`; see
+/// `tetrahedron` for how such values are treated. The vertices at a node with a value at `level`
+/// are merged into one, and the faces that collapse are removed. Where that would make the mesh
+/// non-manifold, for example where the level set has no thickness, the vertices are left in
+/// place, so several of them have the same position.
+///
+/// The normal at a vertex is the sum of the gradients of `u` in the tetrahedra that contain it.
+///
+/// With `options.snap`, vertices close to grid nodes are snapped to them to avoid sliver
+/// triangles. All vertices snapping to the same node are merged into one, unless that would make
+/// the mesh non-manifold around the node, in which case they are left in place.
+pub fn marching_tetrahedra_welded<D, T>(
     u: &[D],
     dim: (usize, usize, usize),
     level: D,
     data: &[T],
-) -> (Vec<[D; 3]>, Vec<[u32; 3]>, Vec<[D; 3]>, Vec<T>)
+    options: &Options<'_, D>,
+) -> Mesh<D, T>
 where
-    D: Interpolate<D>
-        + From<f32>
-        + PartialOrd
-        + std::ops::Sub<D, Output = D>
-        + Copy
-        + Default
-        + Nudge
-        + std::ops::Add<D, Output = D>,
-    T: Interpolate<D> + Default + Copy,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
 {
     let (ni, nj, nk) = dim;
-    assert_eq!(ni * nj * nk, u.len());
-    assert_eq!(ni * nj * nk, data.len());
+    let u = StridedView::row_major(u, [ni, nj, nk]);
+    let data = StridedView::row_major(data, [ni, nj, nk]);
+
+    marching_tetrahedra_welded_strided(u, level, data, options)
+}
+
+/// As `marching_tetrahedra_welded`, with `u` and `data` given by strided views of the same
+/// shape.
+pub fn marching_tetrahedra_welded_strided<D, T>(
+    u: StridedView<D, 3>,
+    level: D,
+    data: StridedView<T, 3>,
+    options: &Options<'_, D>,
+) -> Mesh<D, T>
+where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    marching_tetrahedra_welded_samples(u, level, data, options)
+}
+
+/// As `marching_tetrahedra_welded_strided`, with values `u` of any type that converts into
+/// `D`; see `marching_tetrahedra_samples`.
+pub fn marching_tetrahedra_welded_samples<S, D, T>(
+    u: StridedView<S, 3>,
+    level: D,
+    data: StridedView<T, 3>,
+    options: &Options<'_, D>,
+) -> Mesh<D, T>
+where
+    S: Into<D> + Copy,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    assert_eq!(u.shape(), data.shape());
+    let [ni, nj, nk] = u.shape();
+    let dim = (ni, nj, nk);
 
     let mut verts: Vec<[D; 3]> = Vec::new();
     let mut normals: Vec<[D; 3]> = Vec::new();
     let mut faces: Vec<[u32; 3]> = Vec::new();
     let mut interp_data: Vec<T> = Vec::new();
 
-    // permutations of [0, 1, 2]
-    let perms = [
-        [0, 1, 2],
-        [0, 2, 1],
-        [1, 0, 2],
-        [1, 2, 0],
-        [2, 0, 1],
-        [2, 1, 0],
-    ];
-
-    let strides = [nj * nk, nk, 1];
-
-    let vert_offsets = {
-        let mut vert_offsets = [0; 8];
-
-        let mut c = 0;
-        for i in 0..2 {
-            for j in 0..2 {
-                for k in 0..2 {
-                    vert_offsets[c] = i * nj * nk + j * nk + k;
-                    c += 1;
+    // index of the vertex on the edge between a node below and a node above the level
+    let mut edge_verts: HashMap<(usize, usize), u32> = HashMap::new();
+    // the vertices to be snapped to each node, keyed as the edges
+    let mut snap_verts: HashMap<usize, Vec<u32>> = HashMap::new();
+
+    let mut cuts: Vec<Cut> = Vec::with_capacity(4);
+    let mut tet_faces: Vec<[u32; 3]> = Vec::with_capacity(2);
+
+    for_each_tetrahedron(&u, level, options, |tet, nodes, keys, vs, us| {
+        cuts.clear();
+        tet_faces.clear();
+        tetrahedron(
+            us,
+            [Cut([0, 0]), Cut([1, 1]), Cut([2, 2]), Cut([3, 3])],
+            |c| cuts.push(c),
+            |f| tet_faces.push(tet.orient(f)),
+        );
+
+        if cuts.is_empty() {
+            return;
+        }
+
+        let n = tet.gradient(us);
+
+        let mut idx = [0; 4];
+        for (&Cut([a, b]), idx) in cuts.iter().zip(&mut idx) {
+            *idx = *edge_verts.entry((keys[a], keys[b])).or_insert_with(|| {
+                let v = vs[a].interpolate(&vs[b], us[a], us[b]);
+                verts.push(wrap_position(v, dim, options));
+                interp_data.push(
+                    data.get(nodes[a])
+                        .interpolate(data.get(nodes[b]), us[a], us[b]),
+                );
+                normals.push([D::from(0.); 3]);
+                let v = (verts.len() - 1) as u32;
+
+                // a vertex on a node at the level is always merged with the others there
+                if us[b] == D::from(0.) {
+                    snap_verts.entry(keys[b]).or_default().push(v);
+                } else if let Some(tol) = options.snap {
+                    let t = us[a] / (us[a] - us[b]);
+                    if t <= tol && t < D::from(0.5) {
+                        snap_verts.entry(keys[a]).or_default().push(v);
+                    } else if t >= D::from(1.) - tol {
+                        snap_verts.entry(keys[b]).or_default().push(v);
+                    }
                 }
+
+                v
+            });
+
+            let normal = &mut normals[*idx as usize];
+            for d in 0..3 {
+                normal[d] = normal[d] + n[d];
             }
         }
-        vert_offsets
-    };
 
-    for i in 1..ni {
-        for j in 1..nj {
-            for k in 1..nk {
-                // split each cube into 6 tetrahedra, emit triangles
-                // vertex position
-                let s = (i - 1) * nj * nk + (j - 1) * nk + (k - 1);
-                let ps = [
-                    D::from((i - 1) as f32),
-                    D::from((j - 1) as f32),
-                    D::from((k - 1) as f32),
-                ];
-
-                let n_above = vert_offsets
-                    .iter()
-                    .filter(|&offset| u[s + offset] >= level)
-                    .count();
-
-                if n_above == 0 || n_above == 8 {
-                    continue;
-                }
+        for f in &tet_faces {
+            faces.push([idx[f[0] as usize], idx[f[1] as usize], idx[f[2] as usize]]);
+        }
+    });
 
-                for perm in perms {
-                    // find tetrahedron data by walking along the edges of a cube
-                    // in the order given by the permutation
-                    let (us, vs) = {
-                        let mut us = [D::from(0.); 4];
-                        let mut vs = [([D::from(0.); 3], T::default()); 4];
-                        let mut vi = s;
-                        let mut vp = ps;
-                        us[0] = u[vi] - level;
-                        vs[0] = (vp, data[vi]);
-                        for m in 0..3 {
-                            let t = perm[m];
-                            vi += strides[t];
-                            vp[t] = vp[t] + D::from(1.);
-                            us[m + 1] = u[vi] - level;
-                            vs[m + 1] = (vp, data[vi]);
-                        }
-                        (us, vs)
-                    };
-
-                    let cur = verts.len() as u32;
-                    tetrahedron(
-                        us,
-                        vs,
-                        |(v, d)| {
-                            verts.push(v);
-                            interp_data.push(d);
-                        },
-                        |f| {
-                            faces.push([f[0] + cur, f[1] + cur, f[2] + cur]);
-                        },
-                    );
-
-                    // normals
-                    let mut n = [D::from(0.); 3];
-                    for i in 0..3 {
-                        // invert the permutation
-                        n[perm[i]] = us[i + 1] - us[i];
-                    }
+    if !snap_verts.is_empty() {
+        let mut snap_verts: Vec<_> = snap_verts.into_iter().collect();
+        snap_verts.sort_unstable_by_key(|&(node, _)| node);
+
+        // the keys are indices in the extended grid if unwrapped
+        let (_, kj, kk) = if options.unwrap {
+            extended_dim(dim, options.periodic)
+        } else {
+            dim
+        };
+        let clusters = snap_verts.into_iter().map(|(key, vs)| {
+            let idx = [key / (kj * kk), key / kk % kj, key % kk];
+            let pos = idx.map(|i| D::from(i as f32));
+            let node = [idx[0] % ni, idx[1] % nj, idx[2] % nk];
+            (vs, pos, *data.get(node))
+        });
 
-                    for _ in 0..verts.len() - normals.len() {
-                        normals.push(n);
+        snap::collapse_clusters(
+            clusters,
+            &mut verts,
+            &mut faces,
+            &mut normals,
+            &mut interp_data,
+        );
+    }
+
+    Mesh {
+        verts,
+        faces,
+        normals,
+        data: interp_data,
+    }
+}
+
+/// As `marching_tetrahedra_welded_samples`, but the mesh is passed to `sink` instead of
+/// returned.
+///
+/// The whole mesh is generated before it is passed to `sink`, since the normals and the
+/// snapping depend on all tetrahedra around a vertex.
+pub fn marching_tetrahedra_welded_sink<S, D, T, K>(
+    u: StridedView<S, 3>,
+    level: D,
+    data: StridedView<T, 3>,
+    options: &Options<'_, D>,
+    sink: &mut K,
+) where
+    S: Into<D> + Copy,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+    K: MeshSink<D, T> + ?Sized,
+{
+    let mesh = marching_tetrahedra_welded_samples(u, level, data, options);
+
+    sink.begin();
+    let idx: Vec<u32> = mesh
+        .verts
+        .into_iter()
+        .zip(mesh.normals)
+        .zip(mesh.data)
+        .map(|((v, n), d)| sink.vertex(v, n, d))
+        .collect();
+    for f in mesh.faces {
+        sink.face(f.map(|v| idx[v as usize]));
+    }
+    sink.end();
+}
+
+/// Calls `f` for every tetrahedron of the grid cells that are crossed by the level set.
+///
+/// Tetrahedra with a node that is invalid according to `options` are skipped.
+///
+/// `f` gets the tetrahedron, the indices of its nodes in `u`, the keys identifying its nodes,
+/// their coordinates and the values of `u - level` there. The keys are the positions of the
+/// nodes in the grid in _row-major order_, or with `options.unwrap` in the grid extended by one
+/// node along the periodic axes.
+fn for_each_tetrahedron<S, D, F>(
+    u: &StridedView<S, 3>,
+    level: D,
+    options: &Options<'_, D>,
+    mut f: F,
+) where
+    S: Into<D> + Copy,
+    D: Scalar,
+    F: FnMut(&Tetrahedron, [[usize; 3]; 4], [usize; 4], [[D; 3]; 4], [D; 4]),
+{
+    let cells = Cells::new(u.shape(), options);
+    let (ei, ej, ek) = cells.ext;
+
+    for i in 1..ei {
+        for j in 1..ej {
+            for k in 1..ek {
+                cells.tetrahedra(u, level, options, [i, j, k], &mut f);
+            }
+        }
+    }
+}
+
+/// The cells of the grid extended by one node along the periodic axes, with their tetrahedra.
+///
+/// The cells are indexed by their corner with the largest indices, from `[1, 1, 1]` to the
+/// extended dimension minus one.
+#[derive(Clone, Debug)]
+struct Cells {
+    dim: (usize, usize, usize),
+    ext: (usize, usize, usize),
+    /// The tetrahedra of the even and odd cells.
+    tets: [Vec<Tetrahedron>; 2],
+    /// Offsets of the cell corners in the extended grid.
+    vert_offsets: [usize; 8],
+}
+
+impl Cells {
+    fn new<D>(shape: [usize; 3], options: &Options<'_, D>) -> Cells {
+        let [ni, nj, nk] = shape;
+        let dim = (ni, nj, nk);
+        let ext = extended_dim(dim, options.periodic);
+        let (_, ej, ek) = ext;
+
+        if let Some(mask) = options.mask {
+            assert_eq!(ni * nj * nk, mask.len());
+        }
+
+        if options.decomposition == Decomposition::Five {
+            for (n, p) in [ni, nj, nk].into_iter().zip(options.periodic) {
+                assert!(
+                    !p || n % 2 == 0,
+                    "Decomposition::Five requires an even dimension along periodic axes"
+                );
+            }
+        }
+
+        let tets = [
+            options.decomposition.tetrahedra(false),
+            options.decomposition.tetrahedra(true),
+        ];
+
+        let vert_offsets = {
+            let mut vert_offsets = [0; 8];
+
+            let mut c = 0;
+            for i in 0..2 {
+                for j in 0..2 {
+                    for k in 0..2 {
+                        vert_offsets[c] = i * ej * ek + j * ek + k;
+                        c += 1;
                     }
                 }
             }
+            vert_offsets
+        };
+
+        Cells {
+            dim,
+            ext,
+            tets,
+            vert_offsets,
         }
     }
 
-    (verts, faces, normals, interp_data)
+    /// Calls `f` for every tetrahedron of the cell if it is crossed by the level set, as in
+    /// `for_each_tetrahedron`.
+    fn tetrahedra<S, D, F>(
+        &self,
+        u: &StridedView<S, 3>,
+        level: D,
+        options: &Options<'_, D>,
+        [i, j, k]: [usize; 3],
+        mut f: F,
+    ) where
+        S: Into<D> + Copy,
+        D: Scalar,
+        F: FnMut(&Tetrahedron, [[usize; 3]; 4], [usize; 4], [[D; 3]; 4], [D; 4]),
+    {
+        let (ni, nj, nk) = self.dim;
+        let (_, ej, ek) = self.ext;
+
+        // NaN is the only value not comparable to itself
+        let valid = |n: usize, u: D| {
+            options.mask.is_none_or(|mask| mask[n])
+                && !(options.skip_nan && u.partial_cmp(&u).is_none())
+        };
+
+        // index in the grid of a node of the extended grid
+        let wrap = |i: usize, n: usize| if i == n { 0 } else { i };
+
+        let s = (i - 1) * ej * ek + (j - 1) * ek + (k - 1);
+        let ps = [
+            D::from((i - 1) as f32),
+            D::from((j - 1) as f32),
+            D::from((k - 1) as f32),
+        ];
+
+        let corners = [0, 1, 2, 3, 4, 5, 6, 7].map(|c: usize| {
+            [
+                wrap(i - 1 + (c >> 2 & 1), ni),
+                wrap(j - 1 + (c >> 1 & 1), nj),
+                wrap(k - 1 + (c & 1), nk),
+            ]
+        });
+        let linear = corners.map(|[a, b, c]| a * nj * nk + b * nk + c);
+        let values = corners.map(|n| (*u.get(n)).into());
+
+        let valid_corners = [0, 1, 2, 3, 4, 5, 6, 7].map(|c| valid(linear[c], values[c]));
+        let n_valid = valid_corners.iter().filter(|&&v| v).count();
+        let n_above = values
+            .iter()
+            .zip(&valid_corners)
+            .filter(|&(&u, &v)| v && u >= level)
+            .count();
+
+        if n_above == 0 || n_above == n_valid {
+            return;
+        }
+
+        for tet in &self.tets[(i + j + k - 3) % 2] {
+            if !tet.corners.iter().all(|&c| valid_corners[c]) {
+                continue;
+            }
+
+            let nodes = tet.corners.map(|c| corners[c]);
+            let keys = if options.unwrap {
+                tet.corners.map(|c| s + self.vert_offsets[c])
+            } else {
+                tet.corners.map(|c| linear[c])
+            };
+            let vs = tet.corners.map(|c| {
+                [
+                    ps[0] + D::from((c >> 2 & 1) as f32),
+                    ps[1] + D::from((c >> 1 & 1) as f32),
+                    ps[2] + D::from((c & 1) as f32),
+                ]
+            });
+            let us = tet.corners.map(|c| values[c] - level);
+
+            f(tet, nodes, keys, vs, us);
+        }
+    }
+}
+
+/// The dimension of the grid extended by one node along the periodic axes.
+fn extended_dim(dim: (usize, usize, usize), periodic: [bool; 3]) -> (usize, usize, usize) {
+    (
+        dim.0 + periodic[0] as usize,
+        dim.1 + periodic[1] as usize,
+        dim.2 + periodic[2] as usize,
+    )
+}
+
+/// Moves the coordinates along the periodic axes into the fundamental domain, unless
+/// `options.unwrap` is set.
+fn wrap_position<D: Scalar>(
+    v: [D; 3],
+    dim: (usize, usize, usize),
+    options: &Options<'_, D>,
+) -> [D; 3] {
+    if options.unwrap {
+        return v;
+    }
+
+    let mut v = v;
+    for (d, n) in [dim.0, dim.1, dim.2].into_iter().enumerate() {
+        let n = D::from(n as f32);
+        if options.periodic[d] && v[d] >= n {
+            v[d] = v[d] - n;
+        }
+    }
+    v
 }
 
 /// Emits the triangles of the level set of u intersecting tetrahedra of the mesh.
@@ -325,118 +754,217 @@ where
 /// The coordinate system is chosen so that the node (i, j, k) with index i * dim.1 * dim.2 + j *
 /// dim.2 + k has coordinate
 /// (i, j, k).
-pub fn marching_tetrahedra_with_data_emit<F, D>(
-    u: &[f64],
-    data: &[D],
+pub fn marching_tetrahedra_with_data_emit<F, D, T>(
+    u: &[D],
+    data: &[T],
     dim: (usize, usize, usize),
-    level: f64,
-    mut emit: F,
+    level: D,
+    emit: F,
 ) where
-    F: FnMut([[f64; 3]; 3], [D; 3]),
-    D: Interpolate<f64> + Default + Copy,
+    F: FnMut([[D; 3]; 3], [T; 3]),
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    marching_tetrahedra_with_options_emit(u, data, dim, level, &Options::default(), emit);
+}
+
+/// As `marching_tetrahedra_with_data_emit`, with the cell decomposition, periodicity and valid
+/// nodes given by `options`.
+///
+/// The triangles are oriented as in `marching_tetrahedra_with_options`.
+pub fn marching_tetrahedra_with_options_emit<F, D, T>(
+    u: &[D],
+    data: &[T],
+    dim: (usize, usize, usize),
+    level: D,
+    options: &Options<'_, D>,
+    emit: F,
+) where
+    F: FnMut([[D; 3]; 3], [T; 3]),
+    D: Scalar,
+    T: Interpolate<D> + Copy,
 {
     let (ni, nj, nk) = dim;
-    assert_eq!(ni * nj * nk, u.len());
-    assert_eq!(ni * nj * nk, data.len());
+    let u = StridedView::row_major(u, [ni, nj, nk]);
+    let data = StridedView::row_major(data, [ni, nj, nk]);
 
-    let mut verts: Vec<[f64; 3]> = Vec::with_capacity(4);
-    let mut faces: Vec<[u32; 3]> = Vec::with_capacity(2);
-    let mut interp_data: Vec<D> = Vec::with_capacity(4);
-
-    // permutations of [0, 1, 2]
-    let perms = [
-        [0, 1, 2],
-        [0, 2, 1],
-        [1, 0, 2],
-        [1, 2, 0],
-        [2, 0, 1],
-        [2, 1, 0],
-    ];
-
-    let strides = [nj * nk, nk, 1];
-
-    let vert_offsets = {
-        let mut vert_offsets = [0; 8];
-
-        let mut c = 0;
-        for i in 0..2 {
-            for j in 0..2 {
-                for k in 0..2 {
-                    vert_offsets[c] = i * nj * nk + j * nk + k;
-                    c += 1;
-                }
-            }
-        }
-        vert_offsets
+    marching_tetrahedra_strided_emit(u, data, level, options, emit);
+}
+
+/// As `marching_tetrahedra_with_options_emit`, with `u` and `data` given by strided views of
+/// the same shape.
+///
+/// The values `u` might be of any type that converts into `D`; see
+/// `marching_tetrahedra_samples`.
+pub fn marching_tetrahedra_strided_emit<S, F, D, T>(
+    u: StridedView<S, 3>,
+    data: StridedView<T, 3>,
+    level: D,
+    options: &Options<'_, D>,
+    emit: F,
+) where
+    S: Into<D> + Copy,
+    F: FnMut([[D; 3]; 3], [T; 3]),
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    let mut sink = EmitTriangles {
+        verts: Vec::with_capacity(4),
+        first: 0,
+        faces_started: false,
+        emit,
     };
+    marching_tetrahedra_sink(u, level, data, options, &mut sink);
+}
 
-    for i in 1..ni {
-        for j in 1..nj {
-            for k in 1..nk {
-                // split each cube into 6 tetrahedra, emit triangles
-                // vertex position
-                let s = (i - 1) * nj * nk + (j - 1) * nk + (k - 1);
-                let ps = [(i - 1) as f64, (j - 1) as f64, (k - 1) as f64];
-
-                let n_above = vert_offsets
-                    .iter()
-                    .filter(|&offset| u[s + offset] >= level)
-                    .count();
-
-                if n_above == 0 || n_above == 8 {
-                    continue;
-                }
+/// Passes the triangles generated by `marching_tetrahedra_sink` to a closure.
+///
+/// The driver emits the vertices of every tetrahedron just before its faces, so only the
+/// vertices of the current tetrahedron are kept.
+struct EmitTriangles<D, T, F> {
+    verts: Vec<([D; 3], T)>,
+    /// Index of the first vertex in `verts`.
+    first: u32,
+    faces_started: bool,
+    emit: F,
+}
 
-                for perm in &perms {
-                    // find tetrahedron data by walking along the edges of a cube
-                    // in the order given by the permutation
-                    let (us, vs) = {
-                        let mut us = [0.; 4];
-                        let mut vs = [([0.; 3], D::default()); 4];
-                        let mut vi = s;
-                        let mut vp = ps;
-                        us[0] = u[vi] - level;
-                        vs[0] = (vp, data[vi]);
-                        for m in 0..3 {
-                            let t = perm[m];
-                            vi += strides[t];
-                            vp[t] += 1.;
-                            us[m + 1] = u[vi] - level;
-                            vs[m + 1] = (vp, data[vi]);
-                        }
-                        (us, vs)
-                    };
-
-                    verts.clear();
-                    interp_data.clear();
-                    faces.clear();
-                    tetrahedron(
-                        us,
-                        vs,
-                        |(v, d)| {
-                            verts.push(v);
-                            interp_data.push(d);
-                        },
-                        |f| {
-                            faces.push(f);
-                        },
-                    );
-                    for f in &faces {
-                        emit(
-                            [
-                                verts[f[0] as usize],
-                                verts[f[1] as usize],
-                                verts[f[2] as usize],
-                            ],
-                            [
-                                interp_data[f[0] as usize],
-                                interp_data[f[1] as usize],
-                                interp_data[f[2] as usize],
-                            ],
-                        );
-                    }
-                }
+impl<D, T, F> MeshSink<D, T> for EmitTriangles<D, T, F>
+where
+    D: Copy,
+    T: Copy,
+    F: FnMut([[D; 3]; 3], [T; 3]),
+{
+    fn vertex(&mut self, pos: [D; 3], _normal: [D; 3], data: T) -> u32 {
+        if self.faces_started {
+            self.first += self.verts.len() as u32;
+            self.verts.clear();
+            self.faces_started = false;
+        }
+        self.verts.push((pos, data));
+        self.first + self.verts.len() as u32 - 1
+    }
+
+    fn face(&mut self, face: [u32; 3]) {
+        self.faces_started = true;
+        let vs = face.map(|v| self.verts[(v - self.first) as usize]);
+        (self.emit)(vs.map(|v| v.0), vs.map(|v| v.1));
+    }
+}
+
+/// Returns an iterator over the triangles of the isosurface, with the coordinates and the
+/// interpolated data at their vertices.
+///
+/// The triangles are those emitted by `marching_tetrahedra_strided_emit`, in the same order,
+/// but the cells of the grid are only visited as the iterator advances. Only the triangles of
+/// one cell are kept at a time, so the memory use does not depend on the size of the grid.
+pub fn marching_tetrahedra_iter<'a, S, D, T>(
+    u: StridedView<'a, S, 3>,
+    level: D,
+    data: StridedView<'a, T, 3>,
+    options: &Options<'a, D>,
+) -> TriangleIter<'a, S, D, T>
+where
+    S: Into<D> + Copy,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    assert_eq!(u.shape(), data.shape());
+
+    TriangleIter {
+        u,
+        data,
+        level,
+        options: *options,
+        cells: Cells::new(u.shape(), options),
+        cell: [1, 1, 1],
+        verts: Vec::with_capacity(4),
+        faces: Vec::with_capacity(2),
+        triangles: Vec::new(),
+    }
+}
+
+/// Iterator over the triangles of the isosurface, created by `marching_tetrahedra_iter`.
+pub struct TriangleIter<'a, S, D, T> {
+    u: StridedView<'a, S, 3>,
+    data: StridedView<'a, T, 3>,
+    level: D,
+    options: Options<'a, D>,
+    cells: Cells,
+    /// The next cell to visit.
+    cell: [usize; 3],
+    verts: Vec<([D; 3], T)>,
+    faces: Vec<[u32; 3]>,
+    /// The remaining triangles of the last visited cell, in reverse order.
+    triangles: Vec<([[D; 3]; 3], [T; 3])>,
+}
+
+impl<'a, S, D, T> TriangleIter<'a, S, D, T>
+where
+    S: Into<D> + Copy,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    fn visit(&mut self, cell: [usize; 3]) {
+        let TriangleIter {
+            u,
+            data,
+            level,
+            options,
+            cells,
+            verts,
+            faces,
+            triangles,
+            ..
+        } = self;
+        let dim = cells.dim;
+
+        cells.tetrahedra(u, *level, options, cell, |tet, nodes, _, vs, us| {
+            verts.clear();
+            faces.clear();
+            tetrahedron(
+                us,
+                [0, 1, 2, 3].map(|m| (vs[m], *data.get(nodes[m]))),
+                |(v, d)| verts.push((wrap_position(v, dim, options), d)),
+                |f| faces.push(tet.orient(f)),
+            );
+            for f in faces.iter() {
+                let vs = f.map(|v| verts[v as usize]);
+                triangles.push((vs.map(|v| v.0), vs.map(|v| v.1)));
             }
+        });
+        triangles.reverse();
+    }
+}
+
+impl<'a, S, D, T> Iterator for TriangleIter<'a, S, D, T>
+where
+    S: Into<D> + Copy,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    type Item = ([[D; 3]; 3], [T; 3]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(t) = self.triangles.pop() {
+                return Some(t);
+            }
+
+            let (ei, ej, ek) = self.cells.ext;
+            let [i, j, k] = self.cell;
+            if i >= ei || j >= ej || k >= ek {
+                return None;
+            }
+            self.cell = if k + 1 < ek {
+                [i, j, k + 1]
+            } else if j + 1 < ej {
+                [i, j + 1, 1]
+            } else {
+                [i + 1, 1, 1]
+            };
+
+            self.visit([i, j, k]);
         }
     }
 }
@@ -447,7 +975,7 @@ pub fn marching_tetrahedra_with_data_emit<F, D>(
 /// `corner` is the position of the cube corner with smallest coordinates, so that other corners
 /// can be produces by adding `size`.
 ///
-/// The ordering of `u` and `data` is so that 
+/// The ordering of `u` and `data` is so that
 ///
 /// - `u[i]` and `u[i + 4]` are at corners differing in _x_ (first) coordinate,
 /// - `u[i]` and `u[i + 2]` differ by the _y_ (middle) coordinate,
@@ -457,85 +985,141 @@ pub fn marching_tetrahedra_with_data_emit<F, D>(
 ///
 /// `emit_face` emits the 3 indices of the face vertices.
 /// The emitted face indices are relative to `vertext_index_offset`.
+#[allow(clippy::too_many_arguments)]
 pub fn marching_tetrahedra_with_data_cube<D, T>(
     corner: [D; 3],
     size: D,
     u: [D; 8],
     level: D,
     data: [T; 8],
-    mut vertext_index_offset: u32,
-    mut emit_vertex: impl FnMut([D; 3], [D; 3], T),
-    mut emit_face: impl FnMut([u32; 3]),
+    vertext_index_offset: u32,
+    emit_vertex: impl FnMut([D; 3], [D; 3], T),
+    emit_face: impl FnMut([u32; 3]),
 ) where
-    D: Interpolate<D>
-        + From<f32>
-        + PartialOrd
-        + std::ops::Sub<D, Output = D>
-        + Copy
-        + Default
-        + Nudge
-        + std::ops::Add<D, Output = D>,
-    T: Interpolate<D> + Default + Copy,
+    D: Scalar,
+    T: Interpolate<D> + Copy,
 {
-    // permutations of [0, 1, 2]
-    let perms = [
-        [0, 1, 2],
-        [0, 2, 1],
-        [1, 0, 2],
-        [1, 2, 0],
-        [2, 0, 1],
-        [2, 1, 0],
-    ];
+    marching_tetrahedra_with_data_cube_with_decomposition(
+        corner,
+        size,
+        u,
+        level,
+        data,
+        Decomposition::Six,
+        false,
+        vertext_index_offset,
+        emit_vertex,
+        emit_face,
+    );
+}
 
+/// As `marching_tetrahedra_with_data_cube`, with the cube split into tetrahedra according to
+/// `decomposition`.
+///
+/// `odd` selects the split of `Decomposition::Five`, and should alternate between cubes sharing
+/// a face.
+#[allow(clippy::too_many_arguments)]
+pub fn marching_tetrahedra_with_data_cube_with_decomposition<D, T>(
+    corner: [D; 3],
+    size: D,
+    u: [D; 8],
+    level: D,
+    data: [T; 8],
+    decomposition: Decomposition,
+    odd: bool,
+    vertext_index_offset: u32,
+    emit_vertex: impl FnMut([D; 3], [D; 3], T),
+    emit_face: impl FnMut([u32; 3]),
+) where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+{
+    let mut sink = CubeClosures {
+        next: vertext_index_offset,
+        emit_vertex,
+        emit_face,
+    };
+    marching_tetrahedra_cube_sink(corner, size, u, level, data, decomposition, odd, &mut sink);
+}
+
+/// As `marching_tetrahedra_with_data_cube_with_decomposition`, but the vertices and faces are
+/// passed to `sink`.
+///
+/// `begin` and `end` of `sink` are not called, since the cube is usually one of many.
+#[allow(clippy::too_many_arguments)]
+pub fn marching_tetrahedra_cube_sink<D, T, K>(
+    corner: [D; 3],
+    size: D,
+    u: [D; 8],
+    level: D,
+    data: [T; 8],
+    decomposition: Decomposition,
+    odd: bool,
+    sink: &mut K,
+) where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+    K: MeshSink<D, T> + ?Sized,
+{
     // return early if the cube does not intersect the level set
-    let n_above = u
-        .into_iter()
-        .filter(|&u| u >= level)
-        .count();
+    let n_above = u.into_iter().filter(|&u| u >= level).count();
 
     if n_above == 0 || n_above == 8 {
         return;
     }
 
-    for perm in perms {
-        // find tetrahedron data by walking along the edges of a cube
-        // in the order given by the permutation
-        let (us, vs) = {
-            let mut us = [D::from(0.); 4];
-            let mut vs = [([D::from(0.); 3], T::default()); 4];
-            let mut vi = 0;
+    let mut verts: Vec<([D; 3], T)> = Vec::with_capacity(4);
+    let mut faces: Vec<[u32; 3]> = Vec::with_capacity(2);
+
+    for tet in decomposition.tetrahedra(odd) {
+        let us = tet.corners.map(|c| u[c] - level);
+        let vs = tet.corners.map(|c| {
             let mut vp = corner;
-            us[0] = u[vi] - level;
-            vs[0] = (vp, data[vi]);
-            for m in 0..3 {
-                let t = perm[m];
-                vi += 1 << (2 - t);
-                vp[t] = vp[t] + size;
-                us[m + 1] = u[vi] - level;
-                vs[m + 1] = (vp, data[vi]);
+            for (t, vp) in vp.iter_mut().enumerate() {
+                if c & (1 << (2 - t)) != 0 {
+                    *vp = *vp + size;
+                }
             }
-            (us, vs)
-        };
+            (vp, data[c])
+        });
+
+        verts.clear();
+        faces.clear();
+        tetrahedron(us, vs, |v| verts.push(v), |f| faces.push(tet.orient(f)));
 
         // normal
-        let mut n = [D::from(0.); 3];
-        for i in 0..3 {
-            // invert the permutation
-            n[perm[i]] = us[i + 1] - us[i];
+        let n = tet.gradient(us);
+
+        let mut idx = [0; 4];
+        for (&(v, d), idx) in verts.iter().zip(&mut idx) {
+            *idx = sink.vertex(v, n, d);
         }
+        for f in &faces {
+            sink.face(f.map(|v| idx[v as usize]));
+        }
+    }
+}
 
-        let cur = vertext_index_offset;
-        tetrahedron(
-            us,
-            vs,
-            |(v, d)| {
-                emit_vertex(v, n, d);
-                vertext_index_offset += 1;
-            },
-            |f| {
-                emit_face([f[0] + cur, f[1] + cur, f[2] + cur]);
-            },
-        );
+/// Passes the vertices and faces to the closures of `marching_tetrahedra_with_data_cube`.
+struct CubeClosures<FV, FF> {
+    /// Index of the next vertex.
+    next: u32,
+    emit_vertex: FV,
+    emit_face: FF,
+}
+
+impl<D, T, FV, FF> MeshSink<D, T> for CubeClosures<FV, FF>
+where
+    FV: FnMut([D; 3], [D; 3], T),
+    FF: FnMut([u32; 3]),
+{
+    fn vertex(&mut self, pos: [D; 3], normal: [D; 3], data: T) -> u32 {
+        (self.emit_vertex)(pos, normal, data);
+        self.next += 1;
+        self.next - 1
+    }
 
+    fn face(&mut self, face: [u32; 3]) {
+        (self.emit_face)(face);
     }
 }
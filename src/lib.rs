@@ -9,14 +9,145 @@
 
 mod isoline;
 pub use isoline::marching_triangles;
+pub use isoline::marching_triangles_strided;
+pub use isoline::marching_triangles_strided_emit;
 pub use isoline::marching_triangles_with_data_emit;
+pub use isoline::marching_triangles_with_options;
+pub use isoline::marching_triangles_with_options_emit;
 pub use isoline::Isoline;
+pub use isoline::IsolineOptions;
 
 mod isosurface;
 pub use isosurface::marching_tetrahedra;
 pub use isosurface::marching_tetrahedra_with_data;
 pub use isosurface::marching_tetrahedra_with_data_emit;
+pub use isosurface::marching_tetrahedra_with_options;
+pub use isosurface::marching_tetrahedra_with_options_emit;
 pub use isosurface::marching_tetrahedra_with_data_cube;
+pub use isosurface::marching_tetrahedra_with_data_cube_with_decomposition;
+pub use isosurface::marching_tetrahedra_iter;
+pub use isosurface::marching_tetrahedra_cube_sink;
+pub use isosurface::marching_tetrahedra_welded;
+pub use isosurface::marching_tetrahedra_welded_samples;
+pub use isosurface::marching_tetrahedra_welded_sink;
+pub use isosurface::marching_tetrahedra_welded_strided;
+pub use isosurface::marching_tetrahedra_samples;
+pub use isosurface::marching_tetrahedra_sink;
+pub use isosurface::marching_tetrahedra_strided;
+pub use isosurface::marching_tetrahedra_strided_emit;
+pub use isosurface::Options;
+pub use isosurface::TriangleIter;
 pub use isosurface::tetrahedron;
 
+mod bcc;
+pub use bcc::marching_tetrahedra_bcc;
+pub use bcc::marching_tetrahedra_bcc_resampled;
+pub use bcc::marching_tetrahedra_bcc_sink;
+
+mod decomposition;
+pub use decomposition::Decomposition;
+
 mod interpolate;
+pub use interpolate::Interpolate;
+
+mod scalar;
+pub use scalar::Scalar;
+
+mod interop;
+pub use interop::VectorMesh;
+
+mod mesh;
+pub use mesh::Mesh;
+pub use mesh::Triangles;
+
+mod sink;
+pub use sink::CountingSink;
+pub use sink::MeshSink;
+pub use sink::TriangleSoup;
+
+mod obj;
+pub use obj::read_obj;
+pub use obj::read_obj_with_texture;
+pub use obj::write_obj;
+pub use obj::write_obj_with_texture;
+pub use obj::ObjGrouping;
+pub use obj::ObjOptions;
+pub use obj::ObjWriter;
+
+mod gltf;
+pub use gltf::write_glb;
+pub use gltf::GltfLayout;
+pub use gltf::GltfOptions;
+
+mod attribute;
+pub use attribute::PlyFormat;
+pub use attribute::PlyProperty;
+pub use attribute::PlyType;
+pub use attribute::PlyValue;
+
+mod ply;
+pub use ply::write_ply;
+pub use ply::PlyOptions;
+pub use ply::PlyWriter;
+
+mod stl;
+pub use stl::write_stl;
+pub use stl::StlFormat;
+pub use stl::StlOptions;
+pub use stl::StlWriter;
+
+mod msh;
+pub use msh::write_msh;
+pub use msh::write_msh_isolines;
+pub use msh::MshFormat;
+pub use msh::MshOptions;
+pub use msh::MshPhysicalGroups;
+
+mod vtk;
+pub use vtk::write_vtk;
+pub use vtk::write_vtk_isoline;
+pub use vtk::VtkFormat;
+pub use vtk::VtkOptions;
+
+mod npy;
+pub use npy::read_npy;
+pub use npy::read_npz;
+pub use npy::write_mesh_npz;
+pub use npy::write_npy;
+pub use npy::write_npy_rows;
+pub use npy::NpyArray;
+pub use npy::NpyElement;
+
+mod volume;
+pub use volume::read_metaimage;
+pub use volume::read_metaimage_file;
+pub use volume::read_nifti;
+pub use volume::read_nifti_file;
+pub use volume::read_nrrd;
+pub use volume::read_nrrd_file;
+pub use volume::read_raw;
+pub use volume::ByteOrder;
+pub use volume::RawOptions;
+pub use volume::SampleType;
+pub use volume::Samples;
+pub use volume::Volume;
+
+mod snap;
+
+mod view;
+pub use view::StridedView;
+
+#[cfg(feature = "ndarray")]
+mod array;
+#[cfg(feature = "ndarray")]
+pub use array::marching_tetrahedra_array;
+#[cfg(feature = "ndarray")]
+pub use array::marching_tetrahedra_welded_array;
+#[cfg(feature = "ndarray")]
+pub use array::marching_triangles_array;
+#[cfg(feature = "ndarray")]
+pub use array::to_array2;
+
+mod validate;
+pub use validate::validate_mesh;
+pub use validate::MeshReport;
@@ -0,0 +1,233 @@
+use crate::sink::MeshSink;
+use std::collections::HashMap;
+
+/// A triangular mesh with vertex normals and interpolated data.
+///
+/// The faces are triples of indices into `verts`, and `normals` and `data` have an entry for
+/// every vertex.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mesh<D, T = ()> {
+    pub verts: Vec<[D; 3]>,
+    pub faces: Vec<[u32; 3]>,
+    pub normals: Vec<[D; 3]>,
+    pub data: Vec<T>,
+}
+
+impl<D, T> Mesh<D, T> {
+    pub fn new() -> Mesh<D, T> {
+        Mesh {
+            verts: Vec::new(),
+            faces: Vec::new(),
+            normals: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Splits the mesh into its vertices, faces, normals and data.
+    #[allow(clippy::type_complexity)]
+    pub fn into_raw_parts(self) -> (Vec<[D; 3]>, Vec<[u32; 3]>, Vec<[D; 3]>, Vec<T>) {
+        (self.verts, self.faces, self.normals, self.data)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+
+    /// The positions of the vertices and the data of every face.
+    pub fn triangles(&self) -> Triangles<'_, D, T> {
+        Triangles {
+            mesh: self,
+            face: 0,
+        }
+    }
+
+    /// Moves all vertices and faces of `other` into `self`, leaving `other` empty.
+    ///
+    /// The indices of the faces of `other` are offset by the number of vertices in `self`.
+    pub fn append(&mut self, other: &mut Mesh<D, T>) {
+        let offset = self.verts.len() as u32;
+        self.verts.append(&mut other.verts);
+        self.normals.append(&mut other.normals);
+        self.data.append(&mut other.data);
+        self.faces
+            .extend(other.faces.drain(..).map(|f| f.map(|v| v + offset)));
+    }
+}
+
+impl<D, T> Mesh<D, T>
+where
+    D: Copy + Into<f64>,
+{
+    /// For every vertex, the first vertex with exactly the same position.
+    ///
+    /// Positions are compared bitwise, except that `-0.` equals `0.`.
+    pub(crate) fn welded(&self) -> Vec<u32> {
+        let mut first: HashMap<[u64; 3], u32> = HashMap::new();
+        self.verts
+            .iter()
+            .enumerate()
+            .map(|(v, p)| {
+                // adding zero turns -0. into 0.
+                let key = p.map(|x| (x.into() + 0.).to_bits());
+                *first.entry(key).or_insert(v as u32)
+            })
+            .collect()
+    }
+
+    /// The faces of every connected component, ordered by their first face.
+    ///
+    /// Vertices with the same position are treated as one, so that a mesh with separate
+    /// vertices for every tetrahedron, as generated by `marching_tetrahedra`, has the same
+    /// components as the mesh with shared vertices.
+    pub(crate) fn components(&self) -> Vec<Vec<usize>> {
+        fn find(parent: &mut [usize], a: usize) -> usize {
+            let mut a = a;
+            while parent[a] != a {
+                parent[a] = parent[parent[a]];
+                a = parent[a];
+            }
+            a
+        }
+
+        let n_verts = self.verts.len();
+        let mut parent: Vec<usize> = self.welded().into_iter().map(|v| v as usize).collect();
+        for f in &self.faces {
+            let a = find(&mut parent, f[0] as usize);
+            for &v in &f[1..] {
+                let b = find(&mut parent, v as usize);
+                parent[b] = a;
+            }
+        }
+
+        let mut component_of_root = vec![usize::MAX; n_verts];
+        let mut components: Vec<Vec<usize>> = Vec::new();
+        for (fi, f) in self.faces.iter().enumerate() {
+            let root = find(&mut parent, f[0] as usize);
+            if component_of_root[root] == usize::MAX {
+                component_of_root[root] = components.len();
+                components.push(Vec::new());
+            }
+            components[component_of_root[root]].push(fi);
+        }
+        components
+    }
+}
+
+impl<D, T> Mesh<D, T>
+where
+    D: Copy + PartialOrd,
+{
+    /// The smallest and the largest coordinates of the vertices, or `None` if there are none.
+    pub fn bounding_box(&self) -> Option<[[D; 3]; 2]> {
+        let (first, rest) = self.verts.split_first()?;
+        let mut bb = [*first, *first];
+        for v in rest {
+            for d in 0..3 {
+                if v[d] < bb[0][d] {
+                    bb[0][d] = v[d];
+                }
+                if v[d] > bb[1][d] {
+                    bb[1][d] = v[d];
+                }
+            }
+        }
+        Some(bb)
+    }
+}
+
+impl<D, T> Mesh<D, T>
+where
+    D: Copy
+        + Default
+        + PartialOrd
+        + std::ops::Add<D, Output = D>
+        + std::ops::Sub<D, Output = D>
+        + std::ops::Mul<D, Output = D>,
+{
+    /// Applies the affine map `x ↦ matrix x + translation` to the mesh, for example to go from
+    /// grid indices to physical coordinates.
+    ///
+    /// The normals are multiplied by the cofactor matrix of `matrix`, so they stay orthogonal
+    /// to the faces. If `matrix` reverses orientation, the normals are negated and the faces are
+    /// reversed, so they still point towards the values above the level.
+    pub fn transform(&mut self, matrix: [[D; 3]; 3], translation: [D; 3]) {
+        let mul = |m: &[[D; 3]; 3], v: [D; 3]| {
+            [0, 1, 2].map(|i| m[i][0] * v[0] + m[i][1] * v[1] + m[i][2] * v[2])
+        };
+
+        for v in &mut self.verts {
+            let w = mul(&matrix, *v);
+            *v = [0, 1, 2].map(|i| w[i] + translation[i]);
+        }
+
+        // the cofactor matrix is the inverse transpose times the determinant
+        let m = matrix;
+        let mut cofactor = [0, 1, 2].map(|i| {
+            let (i1, i2) = ((i + 1) % 3, (i + 2) % 3);
+            [0, 1, 2].map(|j| {
+                let (j1, j2) = ((j + 1) % 3, (j + 2) % 3);
+                m[i1][j1] * m[i2][j2] - m[i1][j2] * m[i2][j1]
+            })
+        });
+        let det = m[0][0] * cofactor[0][0] + m[0][1] * cofactor[0][1] + m[0][2] * cofactor[0][2];
+        let zero = D::default();
+        if det < zero {
+            cofactor = cofactor.map(|row| row.map(|c| zero - c));
+            for f in &mut self.faces {
+                f.swap(1, 2);
+            }
+        }
+        for n in &mut self.normals {
+            *n = mul(&cofactor, *n);
+        }
+    }
+}
+
+impl<D, T> Default for Mesh<D, T> {
+    fn default() -> Self {
+        Mesh::new()
+    }
+}
+
+impl<D, T> MeshSink<D, T> for Mesh<D, T> {
+    fn vertex(&mut self, pos: [D; 3], normal: [D; 3], data: T) -> u32 {
+        self.verts.push(pos);
+        self.normals.push(normal);
+        self.data.push(data);
+        (self.verts.len() - 1) as u32
+    }
+
+    fn face(&mut self, face: [u32; 3]) {
+        self.faces.push(face);
+    }
+}
+
+/// Iterator over the triangles of a `Mesh`, created by `Mesh::triangles`.
+pub struct Triangles<'a, D, T = ()> {
+    mesh: &'a Mesh<D, T>,
+    face: usize,
+}
+
+impl<'a, D: Copy, T: Copy> Iterator for Triangles<'a, D, T> {
+    type Item = ([[D; 3]; 3], [T; 3]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let f = *self.mesh.faces.get(self.face)?;
+        self.face += 1;
+        Some((
+            f.map(|v| self.mesh.verts[v as usize]),
+            f.map(|v| self.mesh.data[v as usize]),
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.len();
+        (n, Some(n))
+    }
+}
+
+impl<'a, D: Copy, T: Copy> ExactSizeIterator for Triangles<'a, D, T> {
+    fn len(&self) -> usize {
+        self.mesh.faces.len() - self.face
+    }
+}
@@ -0,0 +1,350 @@
+//! Gmsh `.msh` output, in the version 4.1 format.
+
+use crate::isoline::Isoline;
+use crate::mesh::Mesh;
+use std::io::{self, Write};
+
+/// Encoding of a Gmsh file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MshFormat {
+    Ascii,
+    /// Binary data in little-endian byte order.
+    Binary,
+}
+
+/// How the elements are assigned to physical groups.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MshPhysicalGroups {
+    /// A physical group for every mesh or isoline, for example for every iso-level, with its
+    /// name.
+    Levels,
+    /// A physical group for every connected component, named `<name>_<i>`.
+    Components,
+}
+
+/// Options for the Gmsh writers.
+#[derive(Clone, Copy, Debug)]
+pub struct MshOptions {
+    pub format: MshFormat,
+    pub physical_groups: MshPhysicalGroups,
+}
+
+impl Default for MshOptions {
+    fn default() -> Self {
+        MshOptions {
+            format: MshFormat::Binary,
+            physical_groups: MshPhysicalGroups::Levels,
+        }
+    }
+}
+
+// element types of the Gmsh format
+const LINE: i32 = 1;
+const TRIANGLE: i32 = 2;
+
+/// Writes named meshes, for example the isosurfaces at several levels, as triangles in the Gmsh
+/// format.
+///
+/// Every connected component of a mesh is a separate surface entity, which is assigned to a
+/// physical group as configured by `options`. Vertices with the same position are written as a
+/// single node, so a mesh with separate vertices for every tetrahedron, as generated by
+/// `marching_tetrahedra`, gives the same nodes and entities as the mesh with shared vertices.
+/// Vertices that are not used by any face and the data of the meshes are left out.
+pub fn write_msh<W, D, T>(
+    writer: W,
+    meshes: &[(&str, &Mesh<D, T>)],
+    options: &MshOptions,
+) -> io::Result<()>
+where
+    W: Write,
+    D: Copy + Into<f64>,
+{
+    let mut model = Model::default();
+    for &(name, mesh) in meshes {
+        let components = mesh.components();
+        let welded = mesh.welded();
+        let mut level_tag = None;
+
+        // the index of every vertex in its entity
+        let mut local = vec![usize::MAX; mesh.verts.len()];
+        for (i, faces) in components.iter().enumerate() {
+            let mut entity = Entity {
+                dim: 2,
+                physical: model.physical(2, name, i, &mut level_tag, options.physical_groups),
+                nodes: Vec::new(),
+                elements: Vec::with_capacity(faces.len()),
+            };
+            for &fi in faces {
+                let face = mesh.faces[fi].map(|v| {
+                    let v = welded[v as usize] as usize;
+                    if local[v] == usize::MAX {
+                        local[v] = entity.nodes.len();
+                        entity.nodes.push(mesh.verts[v].map(|x| x.into()));
+                    }
+                    local[v]
+                });
+                entity.elements.push(face.to_vec());
+            }
+            model.entities.push(entity);
+        }
+    }
+
+    model.write(writer, options.format)
+}
+
+/// Writes the components of named isolines as line elements in the plane `z = 0` in the Gmsh
+/// format.
+///
+/// Every component is a separate curve entity, which is assigned to a physical group as
+/// configured by `options`.
+pub fn write_msh_isolines<W, D>(
+    writer: W,
+    isolines: &[(&str, &Isoline<D>)],
+    options: &MshOptions,
+) -> io::Result<()>
+where
+    W: Write,
+    D: Copy + Into<f64> + PartialEq,
+{
+    let mut model = Model::default();
+    for &(name, isoline) in isolines {
+        let mut level_tag = None;
+
+        for (i, c) in isoline.components().enumerate() {
+            // a closed component ends with its first vertex, which becomes a single node
+            let closed = c.len() > 2 && c[0] == c[c.len() - 1];
+            let n = if closed { c.len() - 1 } else { c.len() };
+            if n < 2 {
+                continue;
+            }
+
+            let nodes: Vec<[f64; 3]> = c[..n]
+                .iter()
+                .map(|v| [v[0].into(), v[1].into(), 0.])
+                .collect();
+            let mut elements: Vec<Vec<usize>> = (1..n).map(|k| vec![k - 1, k]).collect();
+            if closed {
+                elements.push(vec![n - 1, 0]);
+            }
+            let physical = model.physical(1, name, i, &mut level_tag, options.physical_groups);
+            model.entities.push(Entity {
+                dim: 1,
+                physical,
+                nodes,
+                elements,
+            });
+        }
+    }
+
+    model.write(writer, options.format)
+}
+
+/// Nodes and elements of a single geometric entity.
+struct Entity {
+    dim: i32,
+    /// The tag of the physical group.
+    physical: i32,
+    nodes: Vec<[f64; 3]>,
+    /// The nodes of every element, as indices into `nodes`.
+    elements: Vec<Vec<usize>>,
+}
+
+#[derive(Default)]
+struct Model {
+    /// Dimension, tag and name of every physical group.
+    groups: Vec<(i32, i32, String)>,
+    entities: Vec<Entity>,
+}
+
+impl Model {
+    /// Adds a physical group, returning its tag.
+    fn group(&mut self, dim: i32, name: String) -> i32 {
+        let tag = self.groups.iter().filter(|g| g.0 == dim).count() as i32 + 1;
+        self.groups.push((dim, tag, name));
+        tag
+    }
+
+    /// The tag of the physical group of the component `i` of the level `name`, adding the group
+    /// of the level to `level_tag` when it is first used.
+    fn physical(
+        &mut self,
+        dim: i32,
+        name: &str,
+        i: usize,
+        level_tag: &mut Option<i32>,
+        groups: MshPhysicalGroups,
+    ) -> i32 {
+        match groups {
+            MshPhysicalGroups::Levels => {
+                *level_tag.get_or_insert_with(|| self.group(dim, name.to_string()))
+            }
+            MshPhysicalGroups::Components => self.group(dim, format!("{name}_{i}")),
+        }
+    }
+
+    fn write<W: Write>(&self, writer: W, format: MshFormat) -> io::Result<()> {
+        let mut w = io::BufWriter::new(writer);
+        let binary = format == MshFormat::Binary;
+        // binary sections end with a newline before the end tag
+        let end_section = |w: &mut io::BufWriter<W>, name: &str| {
+            if binary {
+                writeln!(w)?;
+            }
+            writeln!(w, "$End{name}")
+        };
+
+        writeln!(w, "$MeshFormat")?;
+        writeln!(w, "4.1 {} 8", binary as i32)?;
+        if binary {
+            // allows the reader to detect the byte order
+            w.write_all(&1i32.to_le_bytes())?;
+        }
+        end_section(&mut w, "MeshFormat")?;
+
+        // the names are always written as text
+        if !self.groups.is_empty() {
+            writeln!(w, "$PhysicalNames")?;
+            writeln!(w, "{}", self.groups.len())?;
+            for (dim, tag, name) in &self.groups {
+                writeln!(w, "{} {} \"{}\"", dim, tag, name.replace('"', "'"))?;
+            }
+            writeln!(w, "$EndPhysicalNames")?;
+        }
+
+        writeln!(w, "$Entities")?;
+        let count = |dim| self.entities.iter().filter(|e| e.dim == dim).count() as u64;
+        let counts = [0, 1, 2, 3].map(|dim| Value::Size(count(dim)));
+        write_row(&mut w, &counts, format)?;
+        // entities are numbered in every dimension, in the order of the dimensions
+        for dim in 0..=3 {
+            for (tag, e) in self.entities.iter().filter(|e| e.dim == dim).enumerate() {
+                let mut min = [f64::INFINITY; 3];
+                let mut max = [f64::NEG_INFINITY; 3];
+                for n in &e.nodes {
+                    for d in 0..3 {
+                        min[d] = min[d].min(n[d]);
+                        max[d] = max[d].max(n[d]);
+                    }
+                }
+                let mut row = vec![Value::Int(tag as i32 + 1)];
+                row.extend(min.iter().chain(&max).map(|&x| Value::Double(x)));
+                // one physical tag and no bounding entities
+                row.extend([Value::Size(1), Value::Int(e.physical), Value::Size(0)]);
+                write_row(&mut w, &row, format)?;
+            }
+        }
+        end_section(&mut w, "Entities")?;
+
+        let n_nodes: usize = self.entities.iter().map(|e| e.nodes.len()).sum();
+        let n_elements: usize = self.entities.iter().map(|e| e.elements.len()).sum();
+        let blocks = self.entities.len() as u64;
+        let entity_tags = self.entity_tags();
+
+        writeln!(w, "$Nodes")?;
+        let header = [
+            blocks,
+            n_nodes as u64,
+            n_nodes.min(1) as u64,
+            n_nodes as u64,
+        ];
+        write_row(&mut w, &header.map(Value::Size), format)?;
+        let mut first_node = 1;
+        for (e, &tag) in self.entities.iter().zip(&entity_tags) {
+            let block = [
+                Value::Int(e.dim),
+                Value::Int(tag),
+                Value::Int(0),
+                Value::Size(e.nodes.len() as u64),
+            ];
+            write_row(&mut w, &block, format)?;
+            for i in 0..e.nodes.len() {
+                write_row(&mut w, &[Value::Size((first_node + i) as u64)], format)?;
+            }
+            for n in &e.nodes {
+                write_row(&mut w, &n.map(Value::Double), format)?;
+            }
+            first_node += e.nodes.len();
+        }
+        end_section(&mut w, "Nodes")?;
+
+        writeln!(w, "$Elements")?;
+        let header = [
+            blocks,
+            n_elements as u64,
+            n_elements.min(1) as u64,
+            n_elements as u64,
+        ];
+        write_row(&mut w, &header.map(Value::Size), format)?;
+        let (mut first_node, mut element) = (1, 1);
+        for (e, &tag) in self.entities.iter().zip(&entity_tags) {
+            let ty = if e.dim == 1 { LINE } else { TRIANGLE };
+            let block = [
+                Value::Int(e.dim),
+                Value::Int(tag),
+                Value::Int(ty),
+                Value::Size(e.elements.len() as u64),
+            ];
+            write_row(&mut w, &block, format)?;
+            let mut row = Vec::new();
+            for nodes in &e.elements {
+                row.clear();
+                row.push(Value::Size(element));
+                row.extend(nodes.iter().map(|&n| Value::Size((first_node + n) as u64)));
+                write_row(&mut w, &row, format)?;
+                element += 1;
+            }
+            first_node += e.nodes.len();
+        }
+        end_section(&mut w, "Elements")?;
+
+        w.flush()
+    }
+
+    /// The tag of every entity in its dimension.
+    fn entity_tags(&self) -> Vec<i32> {
+        let mut next = [1; 4];
+        self.entities
+            .iter()
+            .map(|e| {
+                next[e.dim as usize] += 1;
+                next[e.dim as usize] - 1
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Value {
+    Int(i32),
+    Size(u64),
+    Double(f64),
+}
+
+/// Writes the values, on a separate line in the ASCII format.
+fn write_row<W: Write>(w: &mut W, values: &[Value], format: MshFormat) -> io::Result<()> {
+    match format {
+        MshFormat::Ascii => {
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 {
+                    w.write_all(b" ")?;
+                }
+                match v {
+                    Value::Int(x) => write!(w, "{x}")?,
+                    Value::Size(x) => write!(w, "{x}")?,
+                    Value::Double(x) => write!(w, "{x}")?,
+                }
+            }
+            w.write_all(b"\n")
+        }
+        MshFormat::Binary => {
+            for v in values {
+                match v {
+                    Value::Int(x) => w.write_all(&x.to_le_bytes())?,
+                    Value::Size(x) => w.write_all(&x.to_le_bytes())?,
+                    Value::Double(x) => w.write_all(&x.to_le_bytes())?,
+                }
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,591 @@
+//! NumPy `.npy` and `.npz` input and output.
+
+use crate::attribute::PlyProperty;
+use crate::mesh::Mesh;
+use crate::view::StridedView;
+use crate::volume::{decompress, Compression};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Element types of NumPy arrays.
+///
+/// Arrays of any numeric dtype can be read into any element type; the values are converted as
+/// by `as`.
+pub trait NpyElement: Copy {
+    /// The dtype of the element in the `.npy` header, such as `<f8`.
+    const DESCR: &'static str;
+
+    fn from_i64(x: i64) -> Self;
+    fn from_u64(x: u64) -> Self;
+    fn from_f64(x: f64) -> Self;
+
+    /// Appends the little-endian bytes of the element.
+    fn to_le_bytes(self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! impl_npy_element {
+    ($t:ty, $descr:expr) => {
+        impl NpyElement for $t {
+            const DESCR: &'static str = $descr;
+
+            fn from_i64(x: i64) -> Self {
+                x as $t
+            }
+
+            fn from_u64(x: u64) -> Self {
+                x as $t
+            }
+
+            fn from_f64(x: f64) -> Self {
+                x as $t
+            }
+
+            fn to_le_bytes(self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&<$t>::to_le_bytes(self));
+            }
+        }
+    };
+}
+
+impl_npy_element!(i8, "|i1");
+impl_npy_element!(u8, "|u1");
+impl_npy_element!(i16, "<i2");
+impl_npy_element!(u16, "<u2");
+impl_npy_element!(i32, "<i4");
+impl_npy_element!(u32, "<u4");
+impl_npy_element!(i64, "<i8");
+impl_npy_element!(u64, "<u8");
+impl_npy_element!(f32, "<f4");
+impl_npy_element!(f64, "<f8");
+
+/// An array read from a `.npy` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NpyArray<T> {
+    /// The elements in the order of the file.
+    pub values: Vec<T>,
+    pub shape: Vec<usize>,
+    /// Whether the elements are in _column-major order_ (Fortran order) instead of _row-major
+    /// order_ (C order).
+    pub fortran_order: bool,
+}
+
+impl<T> NpyArray<T> {
+    /// A view of the array for the strided drivers, such as `marching_tetrahedra_strided`, in
+    /// either order.
+    ///
+    /// Panics if the array does not have `N` dimensions.
+    pub fn view<const N: usize>(&self) -> StridedView<'_, T, N> {
+        let shape: [usize; N] = self
+            .shape
+            .as_slice()
+            .try_into()
+            .unwrap_or_else(|_| panic!("expected {} dimensions, got {}", N, self.shape.len()));
+        if self.fortran_order {
+            StridedView::column_major(&self.values, shape)
+        } else {
+            StridedView::row_major(&self.values, shape)
+        }
+    }
+}
+
+/// Reads an array in the `.npy` format.
+///
+/// Arrays of booleans, integers and floating point numbers, including half precision, in either
+/// byte order and in C or Fortran order are supported, and converted into `T`.
+pub fn read_npy<R: Read, T: NpyElement>(reader: R) -> io::Result<NpyArray<T>> {
+    let mut reader = reader;
+
+    let mut preamble = [0; 8];
+    reader.read_exact(&mut preamble)?;
+    if &preamble[..6] != b"\x93NUMPY" {
+        return Err(invalid("not a NumPy file"));
+    }
+    let header_len = match preamble[6] {
+        1 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        v => return Err(invalid(format!("unsupported NumPy format version {v}"))),
+    };
+    let header = read_len(&mut reader, header_len as u64)?;
+    let header = String::from_utf8(header).map_err(invalid)?;
+
+    let descr = dict_value(&header, "descr")?;
+    let dtype = Dtype::parse(descr.trim_matches(|c| c == '\'' || c == '"'))?;
+    let fortran_order = match dict_value(&header, "fortran_order")? {
+        "True" => true,
+        "False" => false,
+        v => return Err(invalid(format!("invalid fortran_order `{v}`"))),
+    };
+    let shape = dict_value(&header, "shape")?;
+    let shape = shape
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(|| invalid(format!("invalid shape `{shape}`")))?;
+    let shape = shape
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .map_err(|_| invalid(format!("invalid shape `{shape}`")))
+        })
+        .collect::<io::Result<Vec<usize>>>()?;
+
+    let len = shape
+        .iter()
+        .try_fold(dtype.size, |len, &n| len.checked_mul(n))
+        .ok_or_else(|| invalid("array too large"))?;
+    let bytes = read_len(&mut reader, len as u64)?;
+    let values = bytes
+        .chunks_exact(dtype.size)
+        .map(|b| dtype.decode(b))
+        .collect();
+
+    Ok(NpyArray {
+        values,
+        shape,
+        fortran_order,
+    })
+}
+
+/// Reads the arrays of a `.npz` archive with their names, without the `.npy` extension.
+///
+/// Archives written by `numpy.savez` and, with the `flate2` feature, by
+/// `numpy.savez_compressed` are supported. See `read_npy` for the supported arrays.
+pub fn read_npz<R, T>(reader: R) -> io::Result<Vec<(String, NpyArray<T>)>>
+where
+    R: Read + Seek,
+    T: NpyElement,
+{
+    let mut reader = reader;
+    let entries = zip_entries(&mut reader)?;
+    let len = reader.seek(SeekFrom::End(0))?;
+
+    let mut arrays = Vec::with_capacity(entries.len());
+    for e in entries {
+        // the local header, whose sizes might be missing, followed by the name and extra field
+        reader.seek(SeekFrom::Start(e.offset))?;
+        let mut local = [0; 30];
+        reader.read_exact(&mut local)?;
+        if u32_at(&local, 0) != 0x04034b50 {
+            return Err(invalid("invalid ZIP local header"));
+        }
+        let skip = u16_at(&local, 26) as u64 + u16_at(&local, 28) as u64;
+        let start = e.offset + 30 + skip;
+        let end = start.checked_add(e.compressed_size);
+        if end.is_none_or(|end| end > len) {
+            return Err(invalid(format!("`{}` exceeds the ZIP archive", e.name)));
+        }
+        reader.seek(SeekFrom::Start(start))?;
+
+        let data = read_len(&mut reader, e.compressed_size)?;
+        let data = match e.method {
+            0 => data,
+            8 => {
+                let mut bytes = Vec::new();
+                decompress(&data[..], Compression::Deflate, &mut bytes)?;
+                bytes
+            }
+            m => return Err(invalid(format!("unsupported ZIP compression method {m}"))),
+        };
+        if crc32(&data) != e.crc {
+            return Err(invalid(format!("CRC mismatch in `{}`", e.name)));
+        }
+
+        let name = e.name.strip_suffix(".npy").unwrap_or(&e.name).to_string();
+        arrays.push((name, read_npy(&data[..])?));
+    }
+    Ok(arrays)
+}
+
+/// Writes an array in _row-major order_ in the `.npy` format.
+///
+/// Panics if the number of values does not match the shape.
+pub fn write_npy<W, T>(writer: W, values: &[T], shape: &[usize]) -> io::Result<()>
+where
+    W: Write,
+    T: NpyElement,
+{
+    let mut writer = writer;
+    writer.write_all(&npy_bytes(values, shape))?;
+    writer.flush()
+}
+
+/// Writes the rows of a two-dimensional array, such as the vertices or the faces of a mesh, in
+/// the `.npy` format, with the shape `(rows.len(), N)`.
+pub fn write_npy_rows<W, T, const N: usize>(writer: W, rows: &[[T; N]]) -> io::Result<()>
+where
+    W: Write,
+    T: NpyElement,
+{
+    write_npy(writer, rows.as_flattened(), &[rows.len(), N])
+}
+
+/// Writes a mesh as a `.npz` archive, to be loaded by `numpy.load`, with the arrays `verts` and
+/// `normals` of shape `(n, 3)`, `faces` of shape `(m, 3)` with `uint32` indices, and `data`.
+///
+/// The data is split into scalar columns as described by `PlyProperty` and converted into
+/// `float64`, with the shape `(n, k)` for `k` columns, `(n,)` for a single column, and left out
+/// if there are none.
+pub fn write_mesh_npz<W, D, T>(writer: W, mesh: &Mesh<D, T>) -> io::Result<()>
+where
+    W: Write,
+    D: NpyElement,
+    T: PlyProperty,
+{
+    let mut arrays = vec![
+        (
+            "verts.npy",
+            npy_bytes(mesh.verts.as_flattened(), &[mesh.verts.len(), 3]),
+        ),
+        (
+            "faces.npy",
+            npy_bytes(mesh.faces.as_flattened(), &[mesh.faces.len(), 3]),
+        ),
+        (
+            "normals.npy",
+            npy_bytes(mesh.normals.as_flattened(), &[mesh.normals.len(), 3]),
+        ),
+    ];
+
+    let mut props = Vec::new();
+    T::describe("", &mut props);
+    let columns = props.len();
+    if columns > 0 {
+        let mut data = Vec::with_capacity(mesh.data.len() * columns);
+        let mut values = Vec::with_capacity(columns);
+        for d in &mesh.data {
+            values.clear();
+            d.values(&mut values);
+            data.extend(values.iter().map(|v| v.to_f64()));
+        }
+        let shape = if columns == 1 {
+            vec![mesh.data.len()]
+        } else {
+            vec![mesh.data.len(), columns]
+        };
+        arrays.push(("data.npy", npy_bytes(&data, &shape)));
+    }
+
+    write_zip(writer, &arrays)
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(msg: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// The `.npy` file of an array in _row-major order_.
+fn npy_bytes<T: NpyElement>(values: &[T], shape: &[usize]) -> Vec<u8> {
+    assert_eq!(shape.iter().product::<usize>(), values.len());
+
+    let shape = match shape {
+        [n] => format!("({n},)"),
+        _ => {
+            let s: Vec<String> = shape.iter().map(|n| n.to_string()).collect();
+            format!("({})", s.join(", "))
+        }
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        T::DESCR,
+        shape
+    );
+    // the data is aligned to 64 bytes, and the header ends with a newline
+    let len = 10 + header.len() + 1;
+    header += &" ".repeat(len.next_multiple_of(64) - len);
+    header += "\n";
+
+    let mut bytes = Vec::with_capacity(10 + header.len() + std::mem::size_of_val(values));
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for &v in values {
+        v.to_le_bytes(&mut bytes);
+    }
+    bytes
+}
+
+/// The value of `key` in the Python dictionary literal of a `.npy` header.
+fn dict_value<'a>(header: &'a str, key: &str) -> io::Result<&'a str> {
+    let missing = || invalid(format!("missing `{key}` in the header"));
+    let start = header
+        .find(&format!("'{key}'"))
+        .or_else(|| header.find(&format!("\"{key}\"")))
+        .ok_or_else(missing)?;
+    let rest = header[start + key.len() + 2..].trim_start();
+    let rest = rest.strip_prefix(':').ok_or_else(missing)?.trim_start();
+
+    // the value ends at the closing parenthesis of a tuple, or at the next comma or brace
+    let end = if rest.starts_with('(') {
+        rest.find(')').map(|i| i + 1)
+    } else {
+        rest.find([',', '}'])
+    };
+    Ok(rest[..end.ok_or_else(missing)?].trim())
+}
+
+/// A numeric NumPy dtype.
+#[derive(Clone, Copy, Debug)]
+struct Dtype {
+    kind: u8,
+    size: usize,
+    big_endian: bool,
+}
+
+impl Dtype {
+    fn parse(descr: &str) -> io::Result<Dtype> {
+        let err = || invalid(format!("unsupported dtype `{descr}`"));
+        let (order, rest) = descr.split_at_checked(1).ok_or_else(err)?;
+        let (kind, size) = rest.split_at_checked(1).ok_or_else(err)?;
+        let kind = kind.as_bytes()[0];
+        let size: usize = size.parse().map_err(|_| err())?;
+
+        let supported = match kind {
+            b'b' => size == 1,
+            b'i' | b'u' => matches!(size, 1 | 2 | 4 | 8),
+            b'f' => matches!(size, 2 | 4 | 8),
+            _ => false,
+        };
+        if !supported || !matches!(order, "<" | ">" | "|" | "=") {
+            return Err(err());
+        }
+
+        Ok(Dtype {
+            kind,
+            size,
+            big_endian: order == ">",
+        })
+    }
+
+    fn decode<T: NpyElement>(self, bytes: &[u8]) -> T {
+        // the bytes in little-endian order, extended to 8 bytes
+        let mut b = [0; 8];
+        b[..self.size].copy_from_slice(bytes);
+        if self.big_endian {
+            b[..self.size].reverse();
+        }
+        let bits = u64::from_le_bytes(b);
+
+        match (self.kind, self.size) {
+            (b'i', n) => {
+                // sign extension
+                let shift = 64 - 8 * n as u32;
+                T::from_i64((bits << shift) as i64 >> shift)
+            }
+            (b'f', 2) => T::from_f64(f16_to_f64(bits as u16)),
+            (b'f', 4) => T::from_f64(f32::from_bits(bits as u32).into()),
+            (b'f', _) => T::from_f64(f64::from_bits(bits)),
+            _ => T::from_u64(bits),
+        }
+    }
+}
+
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1. } else { 1. };
+    let exponent = (bits >> 10 & 0x1f) as i32;
+    let fraction = (bits & 0x3ff) as f64;
+    sign * match exponent {
+        0 => fraction * 2f64.powi(-24),
+        0x1f if fraction == 0. => f64::INFINITY,
+        0x1f => f64::NAN,
+        e => (1. + fraction / 1024.) * 2f64.powi(e - 15),
+    }
+}
+
+/// Reads exactly `len` bytes, allocating only as much as is read.
+fn read_len<R: Read>(reader: R, len: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if (bytes.len() as u64) < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "not enough data",
+        ));
+    }
+    Ok(bytes)
+}
+
+fn u16_at(b: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([b[offset], b[offset + 1]])
+}
+
+fn u32_at(b: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(b[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(b: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(b[offset..offset + 8].try_into().unwrap())
+}
+
+/// A file in a ZIP archive, from the central directory.
+struct ZipEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    /// The position of the local header.
+    offset: u64,
+}
+
+/// Reads the central directory of a ZIP archive.
+fn zip_entries<R: Read + Seek>(reader: &mut R) -> io::Result<Vec<ZipEntry>> {
+    // the end of central directory record is at the end, followed by a comment of at most 64 KiB
+    let len = reader.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(22 + 0xffff);
+    reader.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    reader.read_exact(&mut tail)?;
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| u32_at(&tail, i) == 0x06054b50)
+        .ok_or_else(|| invalid("not a ZIP archive"))?;
+    let n_entries = u16_at(&tail, eocd + 10) as usize;
+    let cd_size = u32_at(&tail, eocd + 12) as u64;
+    let cd_offset = u32_at(&tail, eocd + 16) as u64;
+    if n_entries == 0xffff || cd_offset == 0xffffffff {
+        return Err(invalid(
+            "ZIP64 archives with many or large entries are not supported",
+        ));
+    }
+
+    if cd_offset + cd_size > len {
+        return Err(invalid("invalid ZIP central directory"));
+    }
+    reader.seek(SeekFrom::Start(cd_offset))?;
+    let cd = read_len(&mut *reader, cd_size)?;
+
+    let mut entries = Vec::with_capacity(n_entries);
+    let mut p = 0;
+    for _ in 0..n_entries {
+        if cd.len() < p + 46 || u32_at(&cd, p) != 0x02014b50 {
+            return Err(invalid("invalid ZIP central directory"));
+        }
+        let name_len = u16_at(&cd, p + 28) as usize;
+        let extra_len = u16_at(&cd, p + 30) as usize;
+        let comment_len = u16_at(&cd, p + 32) as usize;
+        let end = p + 46 + name_len + extra_len + comment_len;
+        if cd.len() < end {
+            return Err(invalid("invalid ZIP central directory"));
+        }
+
+        let name = String::from_utf8_lossy(&cd[p + 46..p + 46 + name_len]).into_owned();
+        let mut sizes = [u32_at(&cd, p + 24) as u64, u32_at(&cd, p + 20) as u64];
+        let mut offset = u32_at(&cd, p + 42) as u64;
+
+        // the ZIP64 extra field holds the values that do not fit into 32 bits, in this order
+        let extra = &cd[p + 46 + name_len..p + 46 + name_len + extra_len];
+        let mut e = 0;
+        while e + 4 <= extra.len() {
+            let (id, size) = (u16_at(extra, e), u16_at(extra, e + 2) as usize);
+            if id == 1 {
+                let mut field = &extra[e + 4..(e + 4 + size).min(extra.len())];
+                for x in sizes.iter_mut().chain([&mut offset]) {
+                    if *x == 0xffffffff && field.len() >= 8 {
+                        *x = u64_at(field, 0);
+                        field = &field[8..];
+                    }
+                }
+            }
+            e += 4 + size;
+        }
+
+        entries.push(ZipEntry {
+            name,
+            method: u16_at(&cd, p + 10),
+            crc: u32_at(&cd, p + 16),
+            compressed_size: sizes[1],
+            offset,
+        });
+        p = end;
+    }
+    Ok(entries)
+}
+
+/// Writes named files into an uncompressed ZIP archive.
+fn write_zip<W: Write>(writer: W, files: &[(&str, Vec<u8>)]) -> io::Result<()> {
+    let mut w = io::BufWriter::new(writer);
+    // version 2.0, no flags, stored, and the date 1980-01-01 00:00
+    let common = |h: &mut Vec<u8>, name: &str, data: &[u8]| {
+        h.extend_from_slice(&20u16.to_le_bytes());
+        h.extend_from_slice(&[0; 4]);
+        h.extend_from_slice(&0u16.to_le_bytes());
+        h.extend_from_slice(&0x21u16.to_le_bytes());
+        h.extend_from_slice(&crc32(data).to_le_bytes());
+        h.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        h.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        h.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        h.extend_from_slice(&0u16.to_le_bytes());
+    };
+
+    let mut central = Vec::new();
+    let mut offset = 0usize;
+    for (name, data) in files {
+        if data.len() > u32::MAX as usize || offset > u32::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "array too large for a ZIP archive without ZIP64",
+            ));
+        }
+
+        let mut local = 0x04034b50u32.to_le_bytes().to_vec();
+        common(&mut local, name, data);
+        local.extend_from_slice(name.as_bytes());
+        w.write_all(&local)?;
+        w.write_all(data)?;
+
+        central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes());
+        common(&mut central, name, data);
+        // no comment, disk 0, no attributes
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&(offset as u32).to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+
+        offset += local.len() + data.len();
+    }
+
+    w.write_all(&central)?;
+    w.write_all(&0x06054b50u32.to_le_bytes())?;
+    w.write_all(&[0; 4])?;
+    w.write_all(&(files.len() as u16).to_le_bytes())?;
+    w.write_all(&(files.len() as u16).to_le_bytes())?;
+    w.write_all(&(central.len() as u32).to_le_bytes())?;
+    w.write_all(&(offset as u32).to_le_bytes())?;
+    w.write_all(&0u16.to_le_bytes())?;
+    w.flush()
+}
+
+/// The CRC-32 checksum of ZIP archives.
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut c = i as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    c >> 1 ^ 0xedb88320
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[i] = c;
+            i += 1;
+        }
+        table
+    };
+
+    let mut crc = !0u32;
+    for &b in data {
+        crc = TABLE[((crc ^ b as u32) & 0xff) as usize] ^ crc >> 8;
+    }
+    !crc
+}
@@ -0,0 +1,304 @@
+//! Wavefront OBJ input and output.
+
+use crate::mesh::Mesh;
+use crate::scalar::Scalar;
+use crate::sink::{emit_triangle, MeshSink};
+use std::fmt::Display;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::str::FromStr;
+
+/// How the connected components of a mesh are marked in an OBJ file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjGrouping {
+    /// Every component is a separate object, `o component_<i>`.
+    Objects,
+    /// Every component is a separate group, `g component_<i>`.
+    Groups,
+}
+
+/// Options for `write_obj`.
+#[derive(Clone, Copy, Debug)]
+pub struct ObjOptions {
+    /// Write the normals of the vertices as `vn` lines.
+    pub normals: bool,
+    /// Write the faces of every connected component of the mesh as an object or a group.
+    ///
+    /// Vertices with the same position connect their faces, so a mesh with separate vertices
+    /// for every tetrahedron has the same components as the mesh with shared vertices.
+    pub components: Option<ObjGrouping>,
+}
+
+impl Default for ObjOptions {
+    fn default() -> Self {
+        ObjOptions {
+            normals: true,
+            components: None,
+        }
+    }
+}
+
+/// Writes a mesh in the Wavefront OBJ format.
+///
+/// The vertices are written as `v` lines, followed by the normals, and the faces as `f` lines
+/// with the same index for the position and the normal of a vertex. The data of the mesh is
+/// ignored.
+pub fn write_obj<W, D, T>(writer: W, mesh: &Mesh<D, T>, options: &ObjOptions) -> io::Result<()>
+where
+    W: Write,
+    D: Display + Copy + Into<f64>,
+{
+    write_obj_impl(writer, mesh, options, None::<fn(&T) -> D>)
+}
+
+/// As `write_obj`, but also writes a texture coordinate `vt` for every vertex, given by
+/// `texcoord` from its data, for example a scalar field to be shown with a color map.
+pub fn write_obj_with_texture<W, D, T, F>(
+    writer: W,
+    mesh: &Mesh<D, T>,
+    options: &ObjOptions,
+    texcoord: F,
+) -> io::Result<()>
+where
+    W: Write,
+    D: Display + Copy + Into<f64>,
+    F: Fn(&T) -> D,
+{
+    write_obj_impl(writer, mesh, options, Some(texcoord))
+}
+
+fn write_obj_impl<W, D, T, F>(
+    writer: W,
+    mesh: &Mesh<D, T>,
+    options: &ObjOptions,
+    texcoord: Option<F>,
+) -> io::Result<()>
+where
+    W: Write,
+    D: Display + Copy + Into<f64>,
+    F: Fn(&T) -> D,
+{
+    let mut w = BufWriter::new(writer);
+
+    for v in &mesh.verts {
+        writeln!(w, "v {} {} {}", v[0], v[1], v[2])?;
+    }
+    if let Some(texcoord) = &texcoord {
+        for d in &mesh.data {
+            writeln!(w, "vt {} 0", texcoord(d))?;
+        }
+    }
+    if options.normals {
+        for n in &mesh.normals {
+            writeln!(w, "vn {} {} {}", n[0], n[1], n[2])?;
+        }
+    }
+
+    let write_face = |w: &mut BufWriter<W>, f: [u32; 3]| {
+        // indices are 1-based
+        let [a, b, c] = f.map(|v| v + 1);
+        match (texcoord.is_some(), options.normals) {
+            (false, false) => writeln!(w, "f {a} {b} {c}"),
+            (true, false) => writeln!(w, "f {a}/{a} {b}/{b} {c}/{c}"),
+            (false, true) => writeln!(w, "f {a}//{a} {b}//{b} {c}//{c}"),
+            (true, true) => writeln!(w, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}"),
+        }
+    };
+
+    match options.components {
+        None => {
+            for &f in &mesh.faces {
+                write_face(&mut w, f)?;
+            }
+        }
+        Some(grouping) => {
+            let keyword = match grouping {
+                ObjGrouping::Objects => "o",
+                ObjGrouping::Groups => "g",
+            };
+            for (i, faces) in mesh.components().iter().enumerate() {
+                writeln!(w, "{keyword} component_{i}")?;
+                for &fi in faces {
+                    write_face(&mut w, mesh.faces[fi])?;
+                }
+            }
+        }
+    }
+
+    w.flush()
+}
+
+/// Reads a mesh in the Wavefront OBJ format.
+///
+/// Polygonal faces are split into triangles with a common first vertex, and faces with
+/// negative, relative indices are supported. The normal of a vertex is the last normal assigned
+/// to it by a face, or zero. Texture coordinates, groups, materials and other statements are
+/// ignored.
+pub fn read_obj<R, D>(reader: R) -> io::Result<Mesh<D>>
+where
+    R: BufRead,
+    D: FromStr + Copy + Default,
+{
+    let mesh = read_obj_with_texture(reader)?;
+    Ok(Mesh {
+        data: vec![(); mesh.verts.len()],
+        verts: mesh.verts,
+        faces: mesh.faces,
+        normals: mesh.normals,
+    })
+}
+
+/// As `read_obj`, but the data of every vertex is the last texture coordinate assigned to it
+/// by a face, or zero, as written by `write_obj_with_texture`.
+pub fn read_obj_with_texture<R, D>(reader: R) -> io::Result<Mesh<D, D>>
+where
+    R: BufRead,
+    D: FromStr + Copy + Default,
+{
+    let invalid = |line: usize, msg: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: {}", line + 1, msg),
+        )
+    };
+
+    let mut mesh = Mesh::new();
+    let mut texcoords: Vec<D> = Vec::new();
+    let mut normals: Vec<[D; 3]> = Vec::new();
+
+    for (l, line) in reader.lines().enumerate() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        let mut coords = |n: usize| -> io::Result<[D; 3]> {
+            let mut c = [D::default(); 3];
+            for x in c.iter_mut().take(n) {
+                *x = tokens
+                    .next()
+                    .and_then(|t| t.parse().ok())
+                    .ok_or_else(|| invalid(l, "invalid coordinate"))?;
+            }
+            Ok(c)
+        };
+
+        match keyword {
+            "v" => {
+                mesh.verts.push(coords(3)?);
+                mesh.normals.push([D::default(); 3]);
+                mesh.data.push(D::default());
+            }
+            "vt" => texcoords.push(coords(1)?[0]),
+            "vn" => normals.push(coords(3)?),
+            "f" => {
+                // resolves a 1-based or a negative index into a list of length `n`
+                let index = |t: Option<&str>, n: usize| -> io::Result<Option<usize>> {
+                    let t = match t {
+                        None | Some("") => return Ok(None),
+                        Some(t) => t,
+                    };
+                    let i: i64 = t.parse().map_err(|_| invalid(l, "invalid index"))?;
+                    let i = if i < 0 { n as i64 + i } else { i - 1 };
+                    if i < 0 || i >= n as i64 {
+                        return Err(invalid(l, "index out of range"));
+                    }
+                    Ok(Some(i as usize))
+                };
+
+                let mut face: Vec<u32> = Vec::new();
+                for t in line.split_whitespace().skip(1) {
+                    let mut parts = t.split('/');
+                    let v = index(parts.next(), mesh.verts.len())?
+                        .ok_or_else(|| invalid(l, "missing vertex index"))?;
+                    if let Some(vt) = index(parts.next(), texcoords.len())? {
+                        mesh.data[v] = texcoords[vt];
+                    }
+                    if let Some(vn) = index(parts.next(), normals.len())? {
+                        mesh.normals[v] = normals[vn];
+                    }
+                    face.push(v as u32);
+                }
+                if face.len() < 3 {
+                    return Err(invalid(l, "face with less than 3 vertices"));
+                }
+                for m in 1..face.len() - 1 {
+                    mesh.faces.push([face[0], face[m], face[m + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(mesh)
+}
+
+/// Writes a mesh in the Wavefront OBJ format as it is generated.
+///
+/// Every vertex is written as a `v` and a `vn` line, and every face as an `f` line, so nothing
+/// is kept in memory. The data of the vertices is ignored.
+///
+/// Writing stops at the first error, which is returned by `finish`.
+#[derive(Debug)]
+pub struct ObjWriter<W: Write> {
+    writer: W,
+    vertices: u32,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> ObjWriter<W> {
+    pub fn new(writer: W) -> ObjWriter<W> {
+        ObjWriter {
+            writer,
+            vertices: 0,
+            error: None,
+        }
+    }
+
+    /// Flushes the output and returns the underlying writer, or the first error.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Writes a triangle as three separate vertices and a face, for example from the callback
+    /// of `marching_tetrahedra_with_data_emit`.
+    ///
+    /// The normal of the vertices is the cross product of the edges of the triangle, with the
+    /// direction given by the right-hand rule and the length twice its area.
+    pub fn triangle<D: Scalar + Display>(&mut self, t: [[D; 3]; 3]) {
+        emit_triangle::<D, (), _>(self, t, [(); 3]);
+    }
+
+    fn write(&mut self, f: impl FnOnce(&mut W) -> io::Result<()>) {
+        if self.error.is_none() {
+            if let Err(e) = f(&mut self.writer) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+impl<W: Write, D: Display, T> MeshSink<D, T> for ObjWriter<W> {
+    fn vertex(&mut self, pos: [D; 3], normal: [D; 3], _data: T) -> u32 {
+        self.write(|w| {
+            writeln!(w, "v {} {} {}", pos[0], pos[1], pos[2])?;
+            writeln!(w, "vn {} {} {}", normal[0], normal[1], normal[2])
+        });
+        self.vertices += 1;
+        self.vertices - 1
+    }
+
+    fn face(&mut self, face: [u32; 3]) {
+        // indices are 1-based
+        let [a, b, c] = face.map(|v| v + 1);
+        self.write(|w| writeln!(w, "f {a}//{a} {b}//{b} {c}//{c}"));
+    }
+
+    fn end(&mut self) {
+        self.write(|w| w.flush());
+    }
+}
@@ -0,0 +1,334 @@
+//! PLY (Stanford polygon file format) output.
+
+pub use crate::attribute::{PlyFormat, PlyProperty, PlyType, PlyValue};
+use crate::mesh::Mesh;
+use crate::scalar::Scalar;
+use crate::sink::{emit_triangle, MeshSink};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+impl PlyFormat {
+    fn name(self) -> &'static str {
+        match self {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian",
+        }
+    }
+}
+
+impl PlyType {
+    fn name(self) -> &'static str {
+        match self {
+            PlyType::Char => "char",
+            PlyType::UChar => "uchar",
+            PlyType::Short => "short",
+            PlyType::UShort => "ushort",
+            PlyType::Int => "int",
+            PlyType::UInt => "uint",
+            PlyType::Float => "float",
+            PlyType::Double => "double",
+        }
+    }
+}
+
+/// Options for `write_ply`.
+#[derive(Clone, Copy, Debug)]
+pub struct PlyOptions<'a> {
+    pub format: PlyFormat,
+    /// Write the normals of the vertices as the properties `nx`, `ny` and `nz`.
+    ///
+    /// The normals are written as they are in the mesh, without normalization.
+    pub normals: bool,
+    /// The name of the vertex properties of the data; see `PlyProperty`.
+    pub data_name: &'a str,
+}
+
+impl<'a> Default for PlyOptions<'a> {
+    fn default() -> Self {
+        PlyOptions {
+            format: PlyFormat::BinaryLittleEndian,
+            normals: true,
+            data_name: "data",
+        }
+    }
+}
+
+/// Writes a mesh in the PLY format.
+///
+/// The vertices have the properties `x`, `y` and `z`, followed by the normals and the data as
+/// configured by `options`. The faces are written as the list property `vertex_indices`.
+pub fn write_ply<W, D, T>(writer: W, mesh: &Mesh<D, T>, options: &PlyOptions) -> io::Result<()>
+where
+    W: Write,
+    D: PlyProperty,
+    T: PlyProperty,
+{
+    let mut w = BufWriter::new(writer);
+    write_header::<_, D, T>(&mut w, options, mesh.verts.len(), mesh.faces.len())?;
+
+    let mut values = Vec::new();
+    for (v, (n, d)) in mesh.verts.iter().zip(mesh.normals.iter().zip(&mesh.data)) {
+        write_vertex(
+            &mut w,
+            &mut values,
+            v,
+            options.normals.then_some(n),
+            d,
+            options.format,
+        )?;
+    }
+
+    for &f in &mesh.faces {
+        write_face(&mut w, f, options.format)?;
+    }
+
+    w.flush()
+}
+
+/// Writes a mesh in the PLY format as it is generated, so that the mesh is never kept in memory.
+///
+/// The vertices and the faces are kept in temporary files until `finish`, which writes the
+/// header with the numbers of the vertices and the faces, followed by the contents of the
+/// temporary files. The file has the same properties as with `write_ply`.
+///
+/// The writer is a `MeshSink`, to be passed to `marching_tetrahedra_sink`, and `triangle` can be
+/// called from the callback of `marching_tetrahedra_with_data_emit` and the other emit drivers.
+///
+/// Writing stops at the first error, which is returned by `finish`.
+#[derive(Debug)]
+pub struct PlyWriter<W: Write, D, T> {
+    writer: W,
+    format: PlyFormat,
+    normals: bool,
+    data_name: String,
+    vertices: Spool,
+    faces: Spool,
+    n_vertices: u32,
+    n_faces: u32,
+    values: Vec<PlyValue>,
+    error: Option<io::Error>,
+    _marker: PhantomData<fn([D; 3], T)>,
+}
+
+impl<W, D, T> PlyWriter<W, D, T>
+where
+    W: Write,
+    D: PlyProperty,
+    T: PlyProperty,
+{
+    pub fn new(writer: W, options: &PlyOptions) -> io::Result<PlyWriter<W, D, T>> {
+        Ok(PlyWriter {
+            writer,
+            format: options.format,
+            normals: options.normals,
+            data_name: options.data_name.to_string(),
+            vertices: Spool::new()?,
+            faces: Spool::new()?,
+            n_vertices: 0,
+            n_faces: 0,
+            values: Vec::new(),
+            error: None,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Writes a triangle with the data at its vertices as three separate vertices and a face.
+    ///
+    /// The normal of the vertices is the cross product of the edges of the triangle, with the
+    /// direction given by the right-hand rule and the length twice its area.
+    pub fn triangle(&mut self, t: [[D; 3]; 3], data: [T; 3])
+    where
+        D: Scalar,
+    {
+        emit_triangle(self, t, data);
+    }
+
+    /// The number of faces written so far.
+    pub fn len(&self) -> usize {
+        self.n_faces as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n_faces == 0
+    }
+
+    /// Writes the file and returns the underlying writer, or the first error.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+
+        let options = PlyOptions {
+            format: self.format,
+            normals: self.normals,
+            data_name: &self.data_name,
+        };
+        let mut w = BufWriter::new(self.writer);
+        let (n_vertices, n_faces) = (self.n_vertices as usize, self.n_faces as usize);
+        write_header::<_, D, T>(&mut w, &options, n_vertices, n_faces)?;
+        self.vertices.copy_to(&mut w)?;
+        self.faces.copy_to(&mut w)?;
+        w.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+impl<W, D, T> MeshSink<D, T> for PlyWriter<W, D, T>
+where
+    W: Write,
+    D: PlyProperty,
+    T: PlyProperty,
+{
+    fn vertex(&mut self, pos: [D; 3], normal: [D; 3], data: T) -> u32 {
+        if self.error.is_none() {
+            let normal = self.normals.then_some(&normal);
+            let r = write_vertex(
+                &mut self.vertices.file,
+                &mut self.values,
+                &pos,
+                normal,
+                &data,
+                self.format,
+            );
+            if let Err(e) = r {
+                self.error = Some(e);
+            }
+        }
+        self.n_vertices += 1;
+        self.n_vertices - 1
+    }
+
+    fn face(&mut self, face: [u32; 3]) {
+        if self.error.is_none() {
+            match write_face(&mut self.faces.file, face, self.format) {
+                Ok(()) => self.n_faces += 1,
+                Err(e) => self.error = Some(e),
+            }
+        }
+    }
+}
+
+/// A temporary file, removed when dropped.
+#[derive(Debug)]
+struct Spool {
+    file: BufWriter<File>,
+    path: PathBuf,
+}
+
+impl Spool {
+    fn new() -> io::Result<Spool> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let name = format!(
+            "isosurface-{}-{}.tmp",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = env::temp_dir().join(name);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        Ok(Spool {
+            file: BufWriter::new(file),
+            path,
+        })
+    }
+
+    /// Copies the contents of the file to `w`.
+    fn copy_to<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.file.flush()?;
+        let file = self.file.get_mut();
+        file.seek(SeekFrom::Start(0))?;
+        io::copy(file, w)?;
+        Ok(())
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Writes the header of a PLY file with the given numbers of vertices and faces.
+fn write_header<W, D, T>(
+    w: &mut W,
+    options: &PlyOptions,
+    n_vertices: usize,
+    n_faces: usize,
+) -> io::Result<()>
+where
+    W: Write,
+    D: PlyProperty,
+    T: PlyProperty,
+{
+    let mut props = Vec::new();
+    for name in ["x", "y", "z"] {
+        D::describe(name, &mut props);
+    }
+    if options.normals {
+        for name in ["nx", "ny", "nz"] {
+            D::describe(name, &mut props);
+        }
+    }
+    T::describe(options.data_name, &mut props);
+
+    writeln!(w, "ply")?;
+    writeln!(w, "format {} 1.0", options.format.name())?;
+    writeln!(w, "element vertex {}", n_vertices)?;
+    for (name, ty) in &props {
+        writeln!(w, "property {} {}", ty.name(), name)?;
+    }
+    writeln!(w, "element face {}", n_faces)?;
+    writeln!(w, "property list uchar uint vertex_indices")?;
+    writeln!(w, "end_header")
+}
+
+fn write_vertex<W, D, T>(
+    w: &mut W,
+    values: &mut Vec<PlyValue>,
+    pos: &[D; 3],
+    normal: Option<&[D; 3]>,
+    data: &T,
+    format: PlyFormat,
+) -> io::Result<()>
+where
+    W: Write,
+    D: PlyProperty,
+    T: PlyProperty,
+{
+    values.clear();
+    pos.iter().for_each(|x| x.values(values));
+    if let Some(n) = normal {
+        n.iter().for_each(|x| x.values(values));
+    }
+    data.values(values);
+    write_row(w, values, format)
+}
+
+fn write_face<W: Write>(w: &mut W, face: [u32; 3], format: PlyFormat) -> io::Result<()> {
+    let [a, b, c] = face.map(PlyValue::UInt);
+    write_row(w, &[PlyValue::UChar(3), a, b, c], format)
+}
+
+/// Writes the values of an element, on a separate line in the ASCII format.
+fn write_row<W: Write>(w: &mut W, values: &[PlyValue], format: PlyFormat) -> io::Result<()> {
+    for (i, v) in values.iter().enumerate() {
+        if format == PlyFormat::Ascii && i > 0 {
+            w.write_all(b" ")?;
+        }
+        v.write(w, format)?;
+    }
+    if format == PlyFormat::Ascii {
+        w.write_all(b"\n")?;
+    }
+    Ok(())
+}
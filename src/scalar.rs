@@ -0,0 +1,31 @@
+use crate::interpolate::Interpolate;
+
+/// Floating point types in which the level sets are computed, such as `f32` and `f64`.
+///
+/// The trait is implemented for every type with the required comparison, arithmetic and
+/// conversion operations.
+pub trait Scalar:
+    Interpolate<Self>
+    + From<f32>
+    + PartialOrd
+    + Copy
+    + Default
+    + std::ops::Add<Self, Output = Self>
+    + std::ops::Sub<Self, Output = Self>
+    + std::ops::Mul<Self, Output = Self>
+    + std::ops::Div<Self, Output = Self>
+{
+}
+
+impl<D> Scalar for D where
+    D: Interpolate<D>
+        + From<f32>
+        + PartialOrd
+        + Copy
+        + Default
+        + std::ops::Add<D, Output = D>
+        + std::ops::Sub<D, Output = D>
+        + std::ops::Mul<D, Output = D>
+        + std::ops::Div<D, Output = D>
+{
+}
@@ -0,0 +1,133 @@
+use crate::scalar::Scalar;
+
+/// Receiver of the triangular mesh generated by a marching tetrahedra driver.
+///
+/// A driver calls `begin` once, then `vertex` for every vertex and `face` for every face, and
+/// finally `end`. Vertices and faces might be interleaved, but a face only refers to vertices
+/// emitted before it, by the indices returned from `vertex`.
+pub trait MeshSink<D, T> {
+    /// Called before the first vertex.
+    fn begin(&mut self) {}
+
+    /// Adds a vertex with the given position, normal and interpolated data, and returns its
+    /// index.
+    fn vertex(&mut self, pos: [D; 3], normal: [D; 3], data: T) -> u32;
+
+    /// Adds a face given by the indices of its vertices.
+    fn face(&mut self, face: [u32; 3]);
+
+    /// Called after the last face.
+    fn end(&mut self) {}
+}
+
+impl<D, T, K> MeshSink<D, T> for &mut K
+where
+    K: MeshSink<D, T> + ?Sized,
+{
+    fn begin(&mut self) {
+        (**self).begin()
+    }
+
+    fn vertex(&mut self, pos: [D; 3], normal: [D; 3], data: T) -> u32 {
+        (**self).vertex(pos, normal, data)
+    }
+
+    fn face(&mut self, face: [u32; 3]) {
+        (**self).face(face)
+    }
+
+    fn end(&mut self) {
+        (**self).end()
+    }
+}
+
+/// Passes a triangle with the data at its vertices to `sink` as three separate vertices and a
+/// face.
+///
+/// The normal of the vertices is the cross product of the edges of the triangle, with the
+/// direction given by the right-hand rule and the length twice its area.
+pub(crate) fn emit_triangle<D, T, K>(sink: &mut K, t: [[D; 3]; 3], data: [T; 3])
+where
+    D: Scalar,
+    K: MeshSink<D, T> + ?Sized,
+{
+    let e1 = [0, 1, 2].map(|d| t[1][d] - t[0][d]);
+    let e2 = [0, 1, 2].map(|d| t[2][d] - t[0][d]);
+    let n = [
+        e1[1] * e2[2] - e1[2] * e2[1],
+        e1[2] * e2[0] - e1[0] * e2[2],
+        e1[0] * e2[1] - e1[1] * e2[0],
+    ];
+    let [a, b, c] = data;
+    let face = [
+        sink.vertex(t[0], n, a),
+        sink.vertex(t[1], n, b),
+        sink.vertex(t[2], n, c),
+    ];
+    sink.face(face);
+}
+
+/// Collects the triangles of a mesh with the positions and the data at their vertices, without
+/// any shared vertices.
+#[derive(Clone, Debug)]
+pub struct TriangleSoup<D, T = ()> {
+    pub triangles: Vec<[[D; 3]; 3]>,
+    pub data: Vec<[T; 3]>,
+    /// The vertices of the mesh being collected.
+    verts: Vec<([D; 3], T)>,
+}
+
+impl<D, T> TriangleSoup<D, T> {
+    pub fn new() -> TriangleSoup<D, T> {
+        TriangleSoup {
+            triangles: Vec::new(),
+            data: Vec::new(),
+            verts: Vec::new(),
+        }
+    }
+}
+
+impl<D, T> Default for TriangleSoup<D, T> {
+    fn default() -> Self {
+        TriangleSoup::new()
+    }
+}
+
+impl<D: Copy, T: Copy> MeshSink<D, T> for TriangleSoup<D, T> {
+    fn begin(&mut self) {
+        self.verts.clear();
+    }
+
+    fn vertex(&mut self, pos: [D; 3], _normal: [D; 3], data: T) -> u32 {
+        self.verts.push((pos, data));
+        (self.verts.len() - 1) as u32
+    }
+
+    fn face(&mut self, face: [u32; 3]) {
+        let vs = face.map(|v| self.verts[v as usize]);
+        self.triangles.push(vs.map(|v| v.0));
+        self.data.push(vs.map(|v| v.1));
+    }
+
+    fn end(&mut self) {
+        self.verts = Vec::new();
+    }
+}
+
+/// Counts the vertices and faces of a mesh without storing them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CountingSink {
+    pub vertices: usize,
+    pub faces: usize,
+}
+
+impl<D, T> MeshSink<D, T> for CountingSink {
+    fn vertex(&mut self, _pos: [D; 3], _normal: [D; 3], _data: T) -> u32 {
+        self.vertices += 1;
+        (self.vertices - 1) as u32
+    }
+
+    fn face(&mut self, _face: [u32; 3]) {
+        self.faces += 1;
+    }
+}
@@ -0,0 +1,275 @@
+use crate::scalar::Scalar;
+use std::collections::{HashMap, HashSet};
+
+/// The quality, as measured by `validate_mesh`, up to which a face is considered flat.
+const FLAT_QUALITY: f32 = 1e-4;
+
+/// Merges each cluster of vertices into a single vertex with the given position and data.
+///
+/// A cluster is merged only if the faces around the merged vertex form a single fan, that is,
+/// if the mesh stays a 2-manifold. Faces that become degenerate are removed, as are vertices
+/// that are no longer used by any face. The normal of a merged vertex is the sum of the normals of
+/// the cluster. Faces left flat by the merged vertices are removed by edge flips; see
+/// `flip_flat_faces`.
+pub(crate) fn collapse_clusters<D, T, I>(
+    clusters: I,
+    verts: &mut Vec<[D; 3]>,
+    faces: &mut Vec<[u32; 3]>,
+    normals: &mut Vec<[D; 3]>,
+    interp_data: &mut Vec<T>,
+) where
+    D: Scalar,
+    T: Copy,
+    I: Iterator<Item = (Vec<u32>, [D; 3], T)>,
+{
+    let mut vert_faces: Vec<Vec<usize>> = vec![Vec::new(); verts.len()];
+    for (fi, f) in faces.iter().enumerate() {
+        for &v in f {
+            vert_faces[v as usize].push(fi);
+        }
+    }
+    let mut alive = vec![true; faces.len()];
+
+    for (cluster, pos, d) in clusters {
+        let rep = cluster[0];
+
+        let mut cluster_faces: Vec<usize> = cluster
+            .iter()
+            .flat_map(|&v| vert_faces[v as usize].iter().cloned())
+            .filter(|&fi| alive[fi])
+            .collect();
+        cluster_faces.sort_unstable();
+        cluster_faces.dedup();
+
+        let merge = |v: u32| if cluster.contains(&v) { rep } else { v };
+        let merged: Vec<Option<[u32; 3]>> = cluster_faces
+            .iter()
+            .map(|&fi| {
+                let f = faces[fi];
+                let f = [merge(f[0]), merge(f[1]), merge(f[2])];
+                if f[0] != f[1] && f[1] != f[2] && f[2] != f[0] {
+                    Some(f)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let has_boundary = {
+            let mut edges: HashMap<(u32, u32), usize> = HashMap::new();
+            for &fi in &cluster_faces {
+                let f = faces[fi];
+                for m in 0..3 {
+                    let (a, b) = (f[m], f[(m + 1) % 3]);
+                    if cluster.contains(&a) || cluster.contains(&b) {
+                        *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+                    }
+                }
+            }
+            edges.values().any(|&c| c == 1)
+        };
+
+        if !is_fan(rep, merged.iter().flatten(), has_boundary) {
+            continue;
+        }
+
+        for (&fi, f) in cluster_faces.iter().zip(&merged) {
+            match *f {
+                Some(f) => faces[fi] = f,
+                None => alive[fi] = false,
+            }
+        }
+
+        let mut normal = normals[rep as usize];
+        for &v in &cluster[1..] {
+            let n = normals[v as usize];
+            normal = [normal[0] + n[0], normal[1] + n[1], normal[2] + n[2]];
+            vert_faces[v as usize].clear();
+        }
+        verts[rep as usize] = pos;
+        normals[rep as usize] = normal;
+        interp_data[rep as usize] = d;
+        vert_faces[rep as usize] = cluster_faces;
+    }
+
+    let mut fi = 0;
+    faces.retain(|_| {
+        fi += 1;
+        alive[fi - 1]
+    });
+
+    flip_flat_faces(verts, faces);
+    remove_unused_verts(verts, faces, normals, interp_data);
+}
+
+/// Removes flat faces, whose vertices are collinear up to `FLAT_QUALITY`, by flipping their
+/// longest edge.
+///
+/// The vertex opposite to the longest edge lies on it, so the flip splits the neighboring face
+/// across that edge in two at the vertex, which keeps the surface and the vertices in place. A
+/// face is left as it is if its longest edge is on the boundary, if the flipped edge already
+/// exists, or if one of the new faces would be flat as well.
+fn flip_flat_faces<D: Scalar>(verts: &[[D; 3]], faces: &mut [[u32; 3]]) {
+    let sub = |p: [D; 3], q: [D; 3]| [p[0] - q[0], p[1] - q[1], p[2] - q[2]];
+    let dot = |p: [D; 3], q: [D; 3]| p[0] * q[0] + p[1] * q[1] + p[2] * q[2];
+    // the squared quality `12 |ab × bc|² / (|ab|² + |bc|² + |ca|²)²`, compared without roots
+    let is_flat = |f: [u32; 3]| {
+        let [a, b, c] = f.map(|v| verts[v as usize]);
+        let (ab, bc, ca) = (sub(b, a), sub(c, b), sub(a, c));
+        let n = [
+            ab[1] * bc[2] - ab[2] * bc[1],
+            ab[2] * bc[0] - ab[0] * bc[2],
+            ab[0] * bc[1] - ab[1] * bc[0],
+        ];
+        let sides = dot(ab, ab) + dot(bc, bc) + dot(ca, ca);
+        D::from(12.) * dot(n, n) <= D::from(FLAT_QUALITY * FLAT_QUALITY) * sides * sides
+    };
+    let len2 = |a: u32, b: u32| {
+        let d = sub(verts[a as usize], verts[b as usize]);
+        dot(d, d)
+    };
+    let key = |a: u32, b: u32| (a.min(b), a.max(b));
+
+    let mut edge_faces: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (fi, f) in faces.iter().enumerate() {
+        for m in 0..3 {
+            edge_faces
+                .entry(key(f[m], f[(m + 1) % 3]))
+                .or_default()
+                .push(fi);
+        }
+    }
+
+    // every flip removes a flat face without creating one, so this terminates
+    let mut flipped = true;
+    while flipped {
+        flipped = false;
+        for fi in 0..faces.len() {
+            if !is_flat(faces[fi]) {
+                continue;
+            }
+
+            // rotate the face to [a, b, m] with the longest edge a-b
+            let f = faces[fi];
+            let m = (0..3)
+                .max_by(|&i, &j| {
+                    let li = len2(f[(i + 1) % 3], f[(i + 2) % 3]);
+                    let lj = len2(f[(j + 1) % 3], f[(j + 2) % 3]);
+                    li.partial_cmp(&lj).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap();
+            let [a, b, m] = [f[(m + 1) % 3], f[(m + 2) % 3], f[m]];
+
+            let Some(&[f0, f1]) = edge_faces.get(&key(a, b)).map(|fs| &fs[..]) else {
+                continue;
+            };
+            let gi = if f0 == fi { f1 } else { f0 };
+            let g = faces[gi];
+            let x = g.into_iter().find(|&v| v != a && v != b).unwrap();
+
+            // the faces [a, b, m] and [b, a, x] become [a, x, m] and [x, b, m]
+            let new = [[a, x, m], [x, b, m]];
+            if x == m || edge_faces.contains_key(&key(m, x)) || new.iter().any(|&f| is_flat(f)) {
+                continue;
+            }
+
+            for (i, f) in [(fi, f), (gi, g)] {
+                for k in 0..3 {
+                    let fs = edge_faces.get_mut(&key(f[k], f[(k + 1) % 3])).unwrap();
+                    fs.retain(|&j| j != i);
+                }
+            }
+            for (i, f) in [(fi, new[0]), (gi, new[1])] {
+                faces[i] = f;
+                for k in 0..3 {
+                    edge_faces
+                        .entry(key(f[k], f[(k + 1) % 3]))
+                        .or_default()
+                        .push(i);
+                }
+            }
+            flipped = true;
+        }
+    }
+}
+
+/// Checks that the faces around the vertex `v` form a single fan, closed unless `open` is set.
+///
+/// A vertex without any faces passes the check.
+fn is_fan<'a>(v: u32, faces: impl Iterator<Item = &'a [u32; 3]>, open: bool) -> bool {
+    // the edges opposite to `v` must form a simple cycle or a simple path
+    let mut link: HashMap<u32, Vec<u32>> = HashMap::new();
+    let mut seen: HashSet<[u32; 3]> = HashSet::new();
+    for f in faces {
+        let mut key = *f;
+        key.sort_unstable();
+        if !seen.insert(key) {
+            return false;
+        }
+
+        let m = f.iter().position(|&w| w == v).unwrap();
+        let (a, b) = (f[(m + 1) % 3], f[(m + 2) % 3]);
+        link.entry(a).or_default().push(b);
+        link.entry(b).or_default().push(a);
+    }
+
+    let Some(&start) = link.keys().next() else {
+        return true;
+    };
+
+    if link.values().any(|ws| ws.len() > 2) {
+        return false;
+    }
+    let ends = link.values().filter(|ws| ws.len() == 1).count();
+    if ends != 0 && !(open && ends == 2) {
+        return false;
+    }
+
+    // connectedness
+    let mut visited = HashSet::from([start]);
+    let mut stack = vec![start];
+    while let Some(a) = stack.pop() {
+        for &b in &link[&a] {
+            if visited.insert(b) {
+                stack.push(b);
+            }
+        }
+    }
+    visited.len() == link.len()
+}
+
+/// Removes vertices that are not used by any face.
+fn remove_unused_verts<D: Copy, T: Copy>(
+    verts: &mut Vec<[D; 3]>,
+    faces: &mut [[u32; 3]],
+    normals: &mut Vec<[D; 3]>,
+    interp_data: &mut Vec<T>,
+) {
+    let mut used = vec![false; verts.len()];
+    for f in faces.iter() {
+        for &v in f {
+            used[v as usize] = true;
+        }
+    }
+
+    let mut new_index = vec![0; verts.len()];
+    let mut n = 0;
+    for v in 0..verts.len() {
+        if used[v] {
+            new_index[v] = n as u32;
+            verts[n] = verts[v];
+            normals[n] = normals[v];
+            interp_data[n] = interp_data[v];
+            n += 1;
+        }
+    }
+    verts.truncate(n);
+    normals.truncate(n);
+    interp_data.truncate(n);
+
+    for f in faces.iter_mut() {
+        for v in f.iter_mut() {
+            *v = new_index[*v as usize];
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! STL output.
+
+use crate::mesh::Mesh;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+
+/// Encoding of an STL file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StlFormat {
+    Ascii,
+    Binary,
+}
+
+/// Options for the STL writers.
+#[derive(Clone, Copy, Debug)]
+pub struct StlOptions {
+    pub format: StlFormat,
+    /// Distance between the grid nodes along the _x_, _y_ and _z_ axis.
+    ///
+    /// A vertex with coordinates `p` is written at `origin + spacing * p`, for example in
+    /// millimeters instead of grid units.
+    pub spacing: [f64; 3],
+    /// Position of the grid node `(0, 0, 0)`.
+    pub origin: [f64; 3],
+}
+
+impl Default for StlOptions {
+    fn default() -> Self {
+        StlOptions {
+            format: StlFormat::Binary,
+            spacing: [1.; 3],
+            origin: [0.; 3],
+        }
+    }
+}
+
+/// The name of the solid in the ASCII format.
+const SOLID_NAME: &str = "isosurface";
+
+/// Writes a mesh in the STL format.
+///
+/// The normal of every facet is computed from the orientation of the triangle; see
+/// `marching_tetrahedra_with_options`. The data of the mesh is ignored.
+///
+/// Fails with `InvalidInput` if the mesh has more faces than the binary format can count.
+pub fn write_stl<W, D, T>(writer: W, mesh: &Mesh<D, T>, options: &StlOptions) -> io::Result<()>
+where
+    W: Write,
+    D: Copy + Into<f64>,
+{
+    let mut w = BufWriter::new(writer);
+
+    write_header(&mut w, options.format, mesh.faces.len())?;
+    for f in &mesh.faces {
+        let t = f.map(|v| mesh.verts[v as usize]);
+        write_facet(&mut w, t, options)?;
+    }
+    write_footer(&mut w, options.format)?;
+
+    w.flush()
+}
+
+/// Writes triangles in the STL format as they are generated, for example by
+/// `marching_tetrahedra_with_data_emit`, so that the mesh is never kept in memory.
+///
+/// In the binary format the number of triangles is written into the header by `finish`, so the
+/// output must be seekable, such as a file.
+///
+/// Writing stops at the first error, which is returned by `finish`.
+#[derive(Debug)]
+pub struct StlWriter<W: Write + Seek> {
+    writer: BufWriter<W>,
+    options: StlOptions,
+    /// The position of the start of the file in `writer`.
+    start: u64,
+    triangles: usize,
+    error: Option<io::Error>,
+}
+
+impl<W: Write + Seek> StlWriter<W> {
+    pub fn new(writer: W, options: &StlOptions) -> io::Result<StlWriter<W>> {
+        let mut writer = BufWriter::new(writer);
+        let start = writer.stream_position()?;
+        write_header(&mut writer, options.format, 0)?;
+
+        Ok(StlWriter {
+            writer,
+            options: *options,
+            start,
+            triangles: 0,
+            error: None,
+        })
+    }
+
+    /// Writes a triangle with vertices in the grid coordinates.
+    pub fn triangle<D: Copy + Into<f64>>(&mut self, t: [[D; 3]; 3]) {
+        if self.error.is_none() {
+            match write_facet(&mut self.writer, t, &self.options) {
+                Ok(()) => self.triangles += 1,
+                Err(e) => self.error = Some(e),
+            }
+        }
+    }
+
+    /// The number of triangles written so far.
+    pub fn len(&self) -> usize {
+        self.triangles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles == 0
+    }
+
+    /// Completes the file and returns the underlying writer, or the first error.
+    ///
+    /// Fails with `InvalidInput` if more triangles were written than the binary format can
+    /// count.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+
+        let w = &mut self.writer;
+        write_footer(w, self.options.format)?;
+        if self.options.format == StlFormat::Binary {
+            w.seek(SeekFrom::Start(self.start + 80))?;
+            w.write_all(&triangle_count(self.triangles)?.to_le_bytes())?;
+            w.seek(SeekFrom::End(0))?;
+        }
+        w.flush()?;
+
+        self.writer.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+/// The number of triangles as stored in the binary header.
+fn triangle_count(n: usize) -> io::Result<u32> {
+    u32::try_from(n).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "too many triangles for the STL format",
+        )
+    })
+}
+
+fn write_header<W: Write>(w: &mut W, format: StlFormat, triangles: usize) -> io::Result<()> {
+    match format {
+        StlFormat::Ascii => writeln!(w, "solid {SOLID_NAME}"),
+        StlFormat::Binary => {
+            // the header must not start with `solid`
+            let mut header = [0; 80];
+            let text = b"binary STL written by isosurface";
+            header[..text.len()].copy_from_slice(text);
+            w.write_all(&header)?;
+            w.write_all(&triangle_count(triangles)?.to_le_bytes())
+        }
+    }
+}
+
+fn write_footer<W: Write>(w: &mut W, format: StlFormat) -> io::Result<()> {
+    match format {
+        StlFormat::Ascii => writeln!(w, "endsolid {SOLID_NAME}"),
+        StlFormat::Binary => Ok(()),
+    }
+}
+
+/// Writes a facet with the unit normal given by the right-hand rule.
+fn write_facet<W, D>(w: &mut W, t: [[D; 3]; 3], options: &StlOptions) -> io::Result<()>
+where
+    W: Write,
+    D: Copy + Into<f64>,
+{
+    let t = t.map(|v| [0, 1, 2].map(|d| options.origin[d] + options.spacing[d] * v[d].into()));
+
+    let sub = |p: [f64; 3], q: [f64; 3]| [p[0] - q[0], p[1] - q[1], p[2] - q[2]];
+    let (e1, e2) = (sub(t[1], t[0]), sub(t[2], t[0]));
+    let n = [
+        e1[1] * e2[2] - e1[2] * e2[1],
+        e1[2] * e2[0] - e1[0] * e2[2],
+        e1[0] * e2[1] - e1[1] * e2[0],
+    ];
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    let n = if len > 0. { n.map(|x| x / len) } else { n };
+
+    match options.format {
+        StlFormat::Ascii => {
+            writeln!(w, "facet normal {} {} {}", n[0], n[1], n[2])?;
+            writeln!(w, "  outer loop")?;
+            for v in t {
+                writeln!(w, "    vertex {} {} {}", v[0], v[1], v[2])?;
+            }
+            writeln!(w, "  endloop")?;
+            writeln!(w, "endfacet")
+        }
+        StlFormat::Binary => {
+            for x in n.into_iter().chain(t.into_iter().flatten()) {
+                w.write_all(&(x as f32).to_le_bytes())?;
+            }
+            // attribute byte count
+            w.write_all(&[0, 0])
+        }
+    }
+}
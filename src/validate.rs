@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+/// Defects found in a triangular mesh by `validate_mesh`.
+///
+/// Vertices are identified by their coordinates, so every vertex index in the report refers to
+/// the first vertex in the input with the given position.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshReport {
+    /// Number of vertices that have exactly the same coordinates as an earlier vertex.
+    pub duplicate_vertices: usize,
+    /// Vertices with a NaN coordinate.
+    pub nan_vertices: Vec<u32>,
+    /// Edges adjacent to exactly one face.
+    pub boundary_edges: Vec<[u32; 2]>,
+    /// Edges adjacent to more than two faces.
+    pub non_manifold_edges: Vec<[u32; 2]>,
+    /// Vertices whose adjacent faces do not form a single fan.
+    pub non_manifold_vertices: Vec<u32>,
+    /// Faces with quality not above the threshold, including all zero-area faces.
+    pub degenerate_faces: Vec<usize>,
+    /// Edges shared by two faces that traverse it in the same direction.
+    pub inconsistent_edges: Vec<[u32; 2]>,
+}
+
+impl MeshReport {
+    /// Every edge has at most two faces and every vertex is surrounded by a single fan.
+    pub fn is_manifold(&self) -> bool {
+        self.non_manifold_edges.is_empty() && self.non_manifold_vertices.is_empty()
+    }
+
+    /// The mesh is a manifold without boundary.
+    pub fn is_watertight(&self) -> bool {
+        self.is_manifold() && self.boundary_edges.is_empty()
+    }
+
+    /// Adjacent faces are consistently oriented.
+    pub fn is_oriented(&self) -> bool {
+        self.inconsistent_edges.is_empty()
+    }
+
+    /// No defects were found.
+    ///
+    /// Duplicate vertices are not a defect, since the drivers emit separate vertices for every
+    /// tetrahedron; they are only counted in `duplicate_vertices`.
+    pub fn is_valid(&self) -> bool {
+        self.nan_vertices.is_empty()
+            && self.is_watertight()
+            && self.is_oriented()
+            && self.degenerate_faces.is_empty()
+    }
+}
+
+/// Checks a triangular mesh, such as the output of `marching_tetrahedra`, for defects.
+///
+/// The drivers emit separate vertices for every tetrahedron, so vertices with exactly the same
+/// coordinates are merged before the topology is examined; their number is reported in
+/// `duplicate_vertices`.
+///
+/// The quality of a face with area `A` and side lengths `a`, `b`, `c` is `4√3 A / (a² + b² +
+/// c²)`, which is 1 for an equilateral triangle and 0 for a degenerate one. Faces with quality
+/// at most `min_quality` are reported in `degenerate_faces`.
+pub fn validate_mesh<D>(verts: &[[D; 3]], faces: &[[u32; 3]], min_quality: f64) -> MeshReport
+where
+    D: Copy + Into<f64>,
+{
+    let mut report = MeshReport::default();
+
+    let pos = |v: u32| {
+        let p = verts[v as usize];
+        [p[0].into(), p[1].into(), p[2].into()]
+    };
+
+    // merge vertices with identical coordinates
+    let canonical: Vec<u32> = {
+        let mut first: HashMap<[u64; 3], u32> = HashMap::new();
+        (0..verts.len() as u32)
+            .map(|v| {
+                let p = pos(v);
+                if p.iter().any(|x| x.is_nan()) {
+                    report.nan_vertices.push(v);
+                    return v;
+                }
+                // adding zero turns -0. into 0.
+                let key = [
+                    (p[0] + 0.).to_bits(),
+                    (p[1] + 0.).to_bits(),
+                    (p[2] + 0.).to_bits(),
+                ];
+                *first.entry(key).or_insert(v)
+            })
+            .collect()
+    };
+    report.duplicate_vertices = canonical
+        .iter()
+        .enumerate()
+        .filter(|&(v, &c)| v as u32 != c)
+        .count();
+
+    // the number of times each edge is traversed in each direction
+    let mut edges: HashMap<[u32; 2], [usize; 2]> = HashMap::new();
+    let mut vert_faces: HashMap<u32, Vec<usize>> = HashMap::new();
+
+    for (fi, f) in faces.iter().enumerate() {
+        let q = quality(pos(f[0]), pos(f[1]), pos(f[2]));
+        if q.is_nan() || q <= min_quality {
+            report.degenerate_faces.push(fi);
+        }
+
+        let f = [
+            canonical[f[0] as usize],
+            canonical[f[1] as usize],
+            canonical[f[2] as usize],
+        ];
+        if f[0] == f[1] || f[1] == f[2] || f[2] == f[0] {
+            continue;
+        }
+
+        for m in 0..3 {
+            let (a, b) = (f[m], f[(m + 1) % 3]);
+            let count = edges.entry([a.min(b), a.max(b)]).or_insert([0, 0]);
+            count[(a > b) as usize] += 1;
+            vert_faces.entry(f[m]).or_default().push(fi);
+        }
+    }
+
+    for (&e, &[forward, backward]) in &edges {
+        match forward + backward {
+            1 => report.boundary_edges.push(e),
+            2 => {
+                if forward != 1 {
+                    report.inconsistent_edges.push(e);
+                }
+            }
+            _ => report.non_manifold_edges.push(e),
+        }
+    }
+
+    // the faces around a vertex form a fan if the opposite edges form a connected graph
+    for (&v, fs) in &vert_faces {
+        let mut parent: HashMap<u32, u32> = HashMap::new();
+
+        fn find(parent: &mut HashMap<u32, u32>, a: u32) -> u32 {
+            let p = *parent.entry(a).or_insert(a);
+            if p == a {
+                return a;
+            }
+            let r = find(parent, p);
+            parent.insert(a, r);
+            r
+        }
+
+        for &fi in fs {
+            let f = faces[fi];
+            let opposite: Vec<u32> = f
+                .iter()
+                .map(|&w| canonical[w as usize])
+                .filter(|&w| w != v)
+                .collect();
+            let a = find(&mut parent, opposite[0]);
+            let b = find(&mut parent, opposite[1]);
+            parent.insert(a, b);
+        }
+
+        let keys: Vec<u32> = parent.keys().cloned().collect();
+        let mut roots: Vec<u32> = keys.into_iter().map(|w| find(&mut parent, w)).collect();
+        roots.sort_unstable();
+        roots.dedup();
+        if roots.len() > 1 {
+            report.non_manifold_vertices.push(v);
+        }
+    }
+
+    report.boundary_edges.sort_unstable();
+    report.non_manifold_edges.sort_unstable();
+    report.inconsistent_edges.sort_unstable();
+    report.non_manifold_vertices.sort_unstable();
+
+    report
+}
+
+/// Triangle quality, 1 for equilateral triangles and 0 for degenerate ones.
+fn quality(a: [f64; 3], b: [f64; 3], c: [f64; 3]) -> f64 {
+    let sub = |p: [f64; 3], q: [f64; 3]| [p[0] - q[0], p[1] - q[1], p[2] - q[2]];
+    let dot = |p: [f64; 3], q: [f64; 3]| p[0] * q[0] + p[1] * q[1] + p[2] * q[2];
+
+    let (ab, bc, ca) = (sub(b, a), sub(c, b), sub(a, c));
+    let n = [
+        ab[1] * bc[2] - ab[2] * bc[1],
+        ab[2] * bc[0] - ab[0] * bc[2],
+        ab[0] * bc[1] - ab[1] * bc[0],
+    ];
+    // twice the area
+    let area2 = dot(n, n).sqrt();
+    let sides = dot(ab, ab) + dot(bc, bc) + dot(ca, ca);
+
+    if sides > 0. {
+        2. * 3f64.sqrt() * area2 / sides
+    } else {
+        0.
+    }
+}
@@ -0,0 +1,131 @@
+use std::marker::PhantomData;
+
+/// A view of an `N`-dimensional array stored in a slice with arbitrary strides.
+///
+/// The element with index `[i_0, ..., i_{N-1}]` is `data[offset + i_0 * strides[0] + ... +
+/// i_{N-1} * strides[N - 1]]`. The strides are in elements and might be negative, for example
+/// for reversed axes, in which case `offset` is the position of the first element.
+#[derive(Debug)]
+pub struct StridedView<'a, T, const N: usize> {
+    /// The element with index `[0, ..., 0]`.
+    first: *const T,
+    shape: [usize; N],
+    strides: [isize; N],
+    marker: PhantomData<&'a T>,
+}
+
+impl<'a, T, const N: usize> Clone for StridedView<'a, T, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, const N: usize> Copy for StridedView<'a, T, N> {}
+
+// SAFETY: the view only gives shared access to the elements, like `&'a [T]`
+unsafe impl<'a, T: Sync, const N: usize> Send for StridedView<'a, T, N> {}
+unsafe impl<'a, T: Sync, const N: usize> Sync for StridedView<'a, T, N> {}
+
+impl<'a, T, const N: usize> StridedView<'a, T, N> {
+    /// Creates a view with the given `shape` and `strides` starting at `data[offset]`.
+    ///
+    /// Panics if some element of the view is outside of `data`.
+    pub fn new(
+        data: &'a [T],
+        offset: usize,
+        shape: [usize; N],
+        strides: [isize; N],
+    ) -> StridedView<'a, T, N> {
+        if shape.iter().all(|&n| n > 0) {
+            let out_of_bounds = || -> ! { panic!("strided view out of bounds") };
+
+            // the positions of the elements with the smallest and the largest offset, which
+            // also bound the offsets computed by `get`
+            let offset = isize::try_from(offset).unwrap_or_else(|_| out_of_bounds());
+            let (mut min, mut max) = (offset, offset);
+            for (&n, &s) in shape.iter().zip(&strides) {
+                let d = isize::try_from(n - 1)
+                    .ok()
+                    .and_then(|n| n.checked_mul(s))
+                    .unwrap_or_else(|| out_of_bounds());
+                let bound = if d < 0 { &mut min } else { &mut max };
+                *bound = bound.checked_add(d).unwrap_or_else(|| out_of_bounds());
+            }
+            if min < 0 || max as usize >= data.len() {
+                out_of_bounds();
+            }
+        }
+
+        StridedView {
+            first: data.as_ptr().wrapping_add(offset),
+            shape,
+            strides,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates a view from a pointer to the element with index `[0, ..., 0]`.
+    ///
+    /// # Safety
+    ///
+    /// All elements of the view must be valid for reads for the lifetime `'a`.
+    #[cfg(feature = "ndarray")]
+    pub(crate) unsafe fn from_raw_parts(
+        first: *const T,
+        shape: [usize; N],
+        strides: [isize; N],
+    ) -> StridedView<'a, T, N> {
+        StridedView {
+            first,
+            shape,
+            strides,
+            marker: PhantomData,
+        }
+    }
+
+    /// A view of an array in _row-major order_ (C order), where the last index is contiguous.
+    pub fn row_major(data: &'a [T], shape: [usize; N]) -> StridedView<'a, T, N> {
+        assert_eq!(shape.iter().product::<usize>(), data.len());
+        let mut strides = [0; N];
+        let mut s = 1;
+        for d in (0..N).rev() {
+            strides[d] = s as isize;
+            s *= shape[d];
+        }
+        StridedView::new(data, 0, shape, strides)
+    }
+
+    /// A view of an array in _column-major order_ (Fortran order), where the first index is
+    /// contiguous.
+    pub fn column_major(data: &'a [T], shape: [usize; N]) -> StridedView<'a, T, N> {
+        assert_eq!(shape.iter().product::<usize>(), data.len());
+        let mut strides = [0; N];
+        let mut s = 1;
+        for d in 0..N {
+            strides[d] = s as isize;
+            s *= shape[d];
+        }
+        StridedView::new(data, 0, shape, strides)
+    }
+
+    pub fn shape(&self) -> [usize; N] {
+        self.shape
+    }
+
+    pub fn strides(&self) -> [isize; N] {
+        self.strides
+    }
+
+    /// The element with the given index.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn get(&self, index: [usize; N]) -> &'a T {
+        let mut p = 0;
+        for ((&i, &n), &s) in index.iter().zip(&self.shape).zip(&self.strides) {
+            assert!(i < n, "index out of bounds");
+            p += i as isize * s;
+        }
+        // SAFETY: the index is within the shape, and all elements of the view are valid
+        unsafe { &*self.first.offset(p) }
+    }
+}
@@ -0,0 +1,1131 @@
+//! Input of volumes stored as raw samples and in the NRRD, NIfTI and MetaImage formats.
+
+use crate::isosurface::{marching_tetrahedra_samples, Options};
+use crate::mesh::Mesh;
+use crate::view::StridedView;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Type of the samples of a volume.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleType {
+    U8,
+    U16,
+    I16,
+    F32,
+    F64,
+}
+
+impl SampleType {
+    /// The size of a sample in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            SampleType::U8 => 1,
+            SampleType::U16 | SampleType::I16 => 2,
+            SampleType::F32 => 4,
+            SampleType::F64 => 8,
+        }
+    }
+}
+
+/// Byte order of binary samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+/// The samples of a volume, in the type they are stored in.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Samples {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    I16(Vec<i16>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+}
+
+impl Samples {
+    pub fn sample_type(&self) -> SampleType {
+        match self {
+            Samples::U8(_) => SampleType::U8,
+            Samples::U16(_) => SampleType::U16,
+            Samples::I16(_) => SampleType::I16,
+            Samples::F32(_) => SampleType::F32,
+            Samples::F64(_) => SampleType::F64,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Samples::U8(v) => v.len(),
+            Samples::U16(v) => v.len(),
+            Samples::I16(v) => v.len(),
+            Samples::F32(v) => v.len(),
+            Samples::F64(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn to_f64(&self) -> Vec<f64> {
+        match self {
+            Samples::U8(v) => v.iter().map(|&x| x.into()).collect(),
+            Samples::U16(v) => v.iter().map(|&x| x.into()).collect(),
+            Samples::I16(v) => v.iter().map(|&x| x.into()).collect(),
+            Samples::F32(v) => v.iter().map(|&x| x.into()).collect(),
+            Samples::F64(v) => v.clone(),
+        }
+    }
+
+    /// Converts the samples into `f32`, rounding `f64` samples.
+    pub fn to_f32(&self) -> Vec<f32> {
+        match self {
+            Samples::U8(v) => v.iter().map(|&x| x.into()).collect(),
+            Samples::U16(v) => v.iter().map(|&x| x.into()).collect(),
+            Samples::I16(v) => v.iter().map(|&x| x.into()).collect(),
+            Samples::F32(v) => v.clone(),
+            Samples::F64(v) => v.iter().map(|&x| x as f32).collect(),
+        }
+    }
+
+    /// Decodes binary samples.
+    fn from_bytes(bytes: &[u8], ty: SampleType, order: ByteOrder) -> Samples {
+        macro_rules! decode {
+            ($t:ty, $variant:ident) => {
+                Samples::$variant(
+                    bytes
+                        .chunks_exact(std::mem::size_of::<$t>())
+                        .map(|b| {
+                            let b = b.try_into().unwrap();
+                            match order {
+                                ByteOrder::LittleEndian => <$t>::from_le_bytes(b),
+                                ByteOrder::BigEndian => <$t>::from_be_bytes(b),
+                            }
+                        })
+                        .collect(),
+                )
+            };
+        }
+
+        match ty {
+            SampleType::U8 => Samples::U8(bytes.to_vec()),
+            SampleType::U16 => decode!(u16, U16),
+            SampleType::I16 => decode!(i16, I16),
+            SampleType::F32 => decode!(f32, F32),
+            SampleType::F64 => decode!(f64, F64),
+        }
+    }
+
+    /// Applies `slope * x + intercept` to the samples, which are then `f32`, or `f64` if they
+    /// already are.
+    fn scale(self, slope: f64, intercept: f64) -> Samples {
+        let f = |x: f64| slope * x + intercept;
+        match self {
+            Samples::F64(v) => Samples::F64(v.into_iter().map(f).collect()),
+            s => Samples::F32(s.to_f64().into_iter().map(|x| f(x) as f32).collect()),
+        }
+    }
+
+    /// Parses `n` samples separated by whitespace.
+    fn from_text(text: &str, ty: SampleType, n: usize) -> io::Result<Samples> {
+        macro_rules! parse {
+            ($variant:ident) => {{
+                let v = text
+                    .split_whitespace()
+                    .take(n)
+                    .map(|t| {
+                        t.parse()
+                            .map_err(|_| invalid(format!("invalid sample `{t}`")))
+                    })
+                    .collect::<io::Result<Vec<_>>>()?;
+                if v.len() < n {
+                    return Err(invalid("not enough samples"));
+                }
+                Samples::$variant(v)
+            }};
+        }
+
+        Ok(match ty {
+            SampleType::U8 => parse!(U8),
+            SampleType::U16 => parse!(U16),
+            SampleType::I16 => parse!(I16),
+            SampleType::F32 => parse!(F32),
+            SampleType::F64 => parse!(F64),
+        })
+    }
+}
+
+/// A volume of samples on a grid with its position in space.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Volume {
+    /// The samples, with the first axis varying fastest.
+    ///
+    /// The sample with index `[x, y, z]` is at `x + shape[0] * (y + shape[1] * z)`, so the
+    /// samples are in _row-major order_ for the dimensions `(shape[2], shape[1], shape[0])`; see
+    /// `dim`.
+    pub samples: Samples,
+    /// The number of samples along every axis.
+    pub shape: [usize; 3],
+    /// The displacement in space between neighboring samples along every axis.
+    pub directions: [[f64; 3]; 3],
+    /// The position of the sample `[0, 0, 0]` in space.
+    pub origin: [f64; 3],
+}
+
+impl Volume {
+    /// The dimensions of the samples as expected by `marching_tetrahedra` and the other drivers
+    /// that take a slice in _row-major order_, which is the reversed `shape`.
+    ///
+    /// The vertex coordinates `[i, j, k]` of the generated mesh are then the sample indices
+    /// `[z, y, x]`.
+    pub fn dim(&self) -> (usize, usize, usize) {
+        (self.shape[2], self.shape[1], self.shape[0])
+    }
+
+    /// The distance between neighboring samples along every axis.
+    pub fn spacing(&self) -> [f64; 3] {
+        self.directions
+            .map(|d| (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt())
+    }
+
+    /// The affine map from the vertex coordinates of a mesh generated with `dim` to space, as
+    /// the matrix and the translation for `Mesh::transform`.
+    pub fn grid_to_space(&self) -> ([[f64; 3]; 3], [f64; 3]) {
+        // the grid coordinates `[i, j, k]` are the sample indices `[z, y, x]`
+        let d = &self.directions;
+        let matrix = [0, 1, 2].map(|r| [d[2][r], d[1][r], d[0][r]]);
+        (matrix, self.origin)
+    }
+
+    /// Extracts the isosurface at `level` with the vertices in space.
+    ///
+    /// The samples are converted into `f64` as they are read; see `marching_tetrahedra_samples`.
+    /// The grid of `options` has the dimensions `dim`.
+    pub fn marching_tetrahedra(&self, level: f64, options: &Options<'_, f64>) -> Mesh<f64> {
+        let (ni, nj, nk) = self.dim();
+        let shape = [ni, nj, nk];
+        let units = vec![(); self.samples.len()];
+        let data = StridedView::row_major(&units, shape);
+
+        macro_rules! extract {
+            ($v:expr) => {
+                marching_tetrahedra_samples(StridedView::row_major($v, shape), level, data, options)
+            };
+        }
+        let mut mesh = match &self.samples {
+            Samples::U8(v) => extract!(v),
+            Samples::U16(v) => extract!(v),
+            Samples::I16(v) => extract!(v),
+            Samples::F32(v) => extract!(v),
+            Samples::F64(v) => extract!(v),
+        };
+
+        let (matrix, translation) = self.grid_to_space();
+        mesh.transform(matrix, translation);
+        mesh
+    }
+}
+
+/// Options for `read_raw`.
+#[derive(Clone, Copy, Debug)]
+pub struct RawOptions {
+    pub byte_order: ByteOrder,
+    /// Number of bytes before the samples, for example of a header.
+    pub skip: u64,
+    /// Distance between neighboring samples along every axis.
+    pub spacing: [f64; 3],
+    /// Position of the first sample in space.
+    pub origin: [f64; 3],
+}
+
+impl Default for RawOptions {
+    fn default() -> Self {
+        RawOptions {
+            byte_order: ByteOrder::LittleEndian,
+            skip: 0,
+            spacing: [1.; 3],
+            origin: [0.; 3],
+        }
+    }
+}
+
+/// Reads a volume of binary samples with the given shape and type, with the first axis varying
+/// fastest; see `Volume::samples`.
+pub fn read_raw<R: Read>(
+    reader: R,
+    shape: [usize; 3],
+    ty: SampleType,
+    options: &RawOptions,
+) -> io::Result<Volume> {
+    let mut reader = reader;
+    io::copy(&mut (&mut reader).take(options.skip), &mut io::sink())?;
+
+    // the buffer only grows with the data actually read
+    let len = byte_len(sample_count(shape)?, ty)?;
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "not enough data",
+        ));
+    }
+
+    let s = options.spacing;
+    Ok(Volume {
+        samples: Samples::from_bytes(&bytes, ty, options.byte_order),
+        shape,
+        directions: [[s[0], 0., 0.], [0., s[1], 0.], [0., 0., s[2]]],
+        origin: options.origin,
+    })
+}
+
+/// Reads a volume in the NRRD format with the data attached to the header, as in a `.nrrd`
+/// file.
+///
+/// Three-dimensional volumes of the types `uchar`, `ushort`, `short`, `float` and `double` in
+/// the `raw`, `ascii` and `gzip` encodings are supported, where `gzip` requires the `flate2`
+/// feature. The position of the samples is given by the fields `space directions` and `space
+/// origin`, or by `spacings`.
+pub fn read_nrrd<R: BufRead>(reader: R) -> io::Result<Volume> {
+    let mut reader = reader;
+    let header = NrrdHeader::read(&mut reader)?;
+    if header.data_file.is_some() {
+        return Err(invalid("detached data, use `read_nrrd_file`"));
+    }
+    header.read_volume(reader)
+}
+
+/// Reads a volume in the NRRD format from a file, either with attached data or with a detached
+/// header, as in a `.nhdr` file, whose data file is found relative to the header.
+///
+/// See `read_nrrd` for the supported volumes.
+pub fn read_nrrd_file<P: AsRef<Path>>(path: P) -> io::Result<Volume> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = NrrdHeader::read(&mut reader)?;
+    match &header.data_file {
+        None => header.read_volume(reader),
+        Some(data_file) => {
+            let data_path = path.parent().unwrap_or(Path::new("")).join(data_file);
+            header.read_volume(BufReader::new(File::open(data_path)?))
+        }
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(msg: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Raw,
+    Ascii,
+    Gzip,
+}
+
+/// The fields of a NRRD header needed to read the volume.
+#[derive(Debug)]
+struct NrrdHeader {
+    ty: SampleType,
+    shape: [usize; 3],
+    encoding: Encoding,
+    byte_order: Option<ByteOrder>,
+    directions: [[f64; 3]; 3],
+    origin: [f64; 3],
+    data_file: Option<String>,
+    line_skip: usize,
+    /// The number of bytes to skip, or -1 if the data is at the end.
+    byte_skip: i64,
+}
+
+impl NrrdHeader {
+    /// Reads the header up to the empty line before the attached data or to the end.
+    fn read<R: BufRead>(reader: &mut R) -> io::Result<NrrdHeader> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let l = line.trim_end_matches(['\n', '\r']);
+            if l.is_empty() {
+                break;
+            }
+            lines.push(l.to_string());
+        }
+
+        match lines.first() {
+            Some(magic) if magic.starts_with("NRRD000") => {}
+            _ => return Err(invalid("not a NRRD file")),
+        }
+
+        let mut ty = None;
+        let mut dimension = None;
+        let mut sizes = None;
+        let mut encoding = None;
+        let mut header = NrrdHeader {
+            ty: SampleType::U8,
+            shape: [0; 3],
+            encoding: Encoding::Raw,
+            byte_order: None,
+            directions: [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+            origin: [0.; 3],
+            data_file: None,
+            line_skip: 0,
+            byte_skip: 0,
+        };
+
+        for l in &lines[1..] {
+            // comments and key/value pairs are skipped
+            if l.starts_with('#') || l.contains(":=") {
+                continue;
+            }
+            let Some((field, value)) = l.split_once(": ") else {
+                return Err(invalid(format!("invalid header line `{l}`")));
+            };
+            let value = value.trim();
+            let number = |v: &str| {
+                v.parse()
+                    .map_err(|_| invalid(format!("invalid number `{v}`")))
+            };
+
+            match field.to_lowercase().as_str() {
+                "type" => ty = Some(sample_type(value)?),
+                "dimension" => {
+                    dimension = Some(
+                        value
+                            .parse::<usize>()
+                            .map_err(|_| invalid("invalid dimension"))?,
+                    )
+                }
+                "sizes" => {
+                    sizes = Some(
+                        value
+                            .split_whitespace()
+                            .map(|v| v.parse().map_err(|_| invalid("invalid sizes")))
+                            .collect::<io::Result<Vec<usize>>>()?,
+                    )
+                }
+                "encoding" => {
+                    encoding = Some(match value {
+                        "raw" => Encoding::Raw,
+                        "ascii" | "text" | "txt" => Encoding::Ascii,
+                        "gzip" | "gz" => Encoding::Gzip,
+                        _ => return Err(invalid(format!("unsupported encoding `{value}`"))),
+                    })
+                }
+                "endian" => {
+                    header.byte_order = Some(match value {
+                        "little" => ByteOrder::LittleEndian,
+                        "big" => ByteOrder::BigEndian,
+                        _ => return Err(invalid(format!("invalid endian `{value}`"))),
+                    })
+                }
+                "spacings" => {
+                    for (a, v) in value.split_whitespace().take(3).enumerate() {
+                        // unknown spacings are left at 1
+                        if v != "nan" && v != "NaN" {
+                            header.directions[a] = [0.; 3];
+                            header.directions[a][a] = number(v)?;
+                        }
+                    }
+                }
+                "space directions" => {
+                    let vectors: Vec<&str> = value.split_whitespace().collect();
+                    if vectors.len() < 3 {
+                        return Err(invalid("invalid space directions"));
+                    }
+                    for (a, v) in vectors.into_iter().take(3).enumerate() {
+                        if v != "none" {
+                            header.directions[a] = vector(v)?;
+                        }
+                    }
+                }
+                "space origin" => header.origin = vector(value)?,
+                "data file" | "datafile" => {
+                    if value.starts_with("LIST") || value.split_whitespace().count() > 1 {
+                        return Err(invalid("multiple data files are not supported"));
+                    }
+                    header.data_file = Some(value.to_string());
+                }
+                "line skip" | "lineskip" => {
+                    header.line_skip = value.parse().map_err(|_| invalid("invalid line skip"))?
+                }
+                "byte skip" | "byteskip" => {
+                    header.byte_skip = value.parse().map_err(|_| invalid("invalid byte skip"))?
+                }
+                _ => {}
+            }
+        }
+
+        header.ty = ty.ok_or_else(|| invalid("missing type"))?;
+        header.encoding = encoding.ok_or_else(|| invalid("missing encoding"))?;
+        if dimension != Some(3) {
+            return Err(invalid("only 3-dimensional volumes are supported"));
+        }
+        match sizes.as_deref() {
+            Some(&[a, b, c]) => header.shape = [a, b, c],
+            _ => return Err(invalid("invalid sizes")),
+        }
+        if header.byte_skip < -1 || (header.byte_skip == -1 && header.encoding != Encoding::Raw) {
+            return Err(invalid("invalid byte skip"));
+        }
+        Ok(header)
+    }
+
+    /// Reads the samples from the data following the header.
+    fn read_volume<R: BufRead>(&self, reader: R) -> io::Result<Volume> {
+        let mut reader = reader;
+        let mut line = Vec::new();
+        for _ in 0..self.line_skip {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+        }
+
+        let n = sample_count(self.shape)?;
+
+        // raw data is read up to its end, data at the end of the file needs all of it
+        let mut bytes = Vec::new();
+        match self.encoding {
+            Encoding::Raw if self.byte_skip >= 0 => {
+                let len = (self.byte_skip as u64).saturating_add(byte_len(n, self.ty)? as u64);
+                reader.take(len).read_to_end(&mut bytes)?;
+            }
+            Encoding::Raw | Encoding::Ascii => {
+                reader.read_to_end(&mut bytes)?;
+            }
+            Encoding::Gzip => decompress(reader, Compression::Gzip, &mut bytes)?,
+        }
+        let samples = match self.encoding {
+            Encoding::Ascii => {
+                let text = std::str::from_utf8(&bytes).map_err(invalid)?;
+                Samples::from_text(text, self.ty, n)?
+            }
+            Encoding::Raw | Encoding::Gzip => {
+                binary_samples(&bytes, self.ty, n, self.byte_order, self.byte_skip)?
+            }
+        };
+
+        Ok(Volume {
+            samples,
+            shape: self.shape,
+            directions: self.directions,
+            origin: self.origin,
+        })
+    }
+}
+
+/// Reads a volume in the NIfTI-1 or NIfTI-2 format from a single file, as a `.nii` file or a
+/// gzip compressed `.nii.gz` file, where compressed files require the `flate2` feature.
+///
+/// Volumes of the types `uint8`, `uint16`, `int16`, `float32` and `float64` with at most 3
+/// dimensions are supported. The position of the samples is given by the sform if its code is
+/// positive, otherwise by the qform if its code is positive, otherwise by the voxel sizes only,
+/// as in the NIfTI reference implementation. The coordinates are in the RAS+ space of NIfTI.
+///
+/// If `scl_slope` is nonzero, the samples are scaled by it and offset by `scl_inter`, giving
+/// `f32` samples, or `f64` samples for `float64` volumes.
+pub fn read_nifti<R: Read>(reader: R) -> io::Result<Volume> {
+    let bytes = read_maybe_gzip(reader)?;
+    let header = NiftiHeader::parse(&bytes)?;
+    if !header.single_file {
+        return Err(invalid("separate header and image, use `read_nifti_file`"));
+    }
+    header.read_volume(&bytes)
+}
+
+/// Reads a volume in the NIfTI-1 or NIfTI-2 format from a file, either a single `.nii` or
+/// `.nii.gz` file, or a `.hdr` header with its `.img` image.
+///
+/// See `read_nifti` for the supported volumes.
+pub fn read_nifti_file<P: AsRef<Path>>(path: P) -> io::Result<Volume> {
+    let path = path.as_ref();
+    let bytes = read_maybe_gzip(File::open(path)?)?;
+    let header = NiftiHeader::parse(&bytes)?;
+    if header.single_file {
+        return header.read_volume(&bytes);
+    }
+
+    // the image of `a.hdr` is `a.img`, and of `a.hdr.gz` is `a.img.gz`
+    let name = path.to_string_lossy();
+    let image = if let Some(stem) = name.strip_suffix(".hdr.gz") {
+        format!("{stem}.img.gz")
+    } else {
+        format!("{}.img", name.strip_suffix(".hdr").unwrap_or(&name))
+    };
+    header.read_volume(&read_maybe_gzip(File::open(image)?)?)
+}
+
+/// Reads all data, decompressing it if it starts with the gzip magic number.
+fn read_maybe_gzip<R: Read>(reader: R) -> io::Result<Vec<u8>> {
+    let mut reader = BufReader::new(reader);
+    let mut bytes = Vec::new();
+    if reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        decompress(reader, Compression::Gzip, &mut bytes)?;
+    } else {
+        reader.read_to_end(&mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+/// Little- or big-endian fields of a binary header.
+struct Fields<'a> {
+    bytes: &'a [u8],
+    byte_order: ByteOrder,
+}
+
+macro_rules! field {
+    ($name:ident, $t:ty) => {
+        fn $name(&self, offset: usize) -> $t {
+            let b = self.bytes[offset..offset + std::mem::size_of::<$t>()]
+                .try_into()
+                .unwrap();
+            match self.byte_order {
+                ByteOrder::LittleEndian => <$t>::from_le_bytes(b),
+                ByteOrder::BigEndian => <$t>::from_be_bytes(b),
+            }
+        }
+    };
+}
+
+impl<'a> Fields<'a> {
+    field!(i16, i16);
+    field!(i32, i32);
+    field!(i64, i64);
+    field!(f32, f32);
+    field!(f64, f64);
+}
+
+/// The fields of a NIfTI header needed to read the volume.
+#[derive(Debug)]
+struct NiftiHeader {
+    ty: SampleType,
+    byte_order: ByteOrder,
+    shape: [usize; 3],
+    /// Whether the image follows the header in the same file.
+    single_file: bool,
+    vox_offset: usize,
+    slope: f64,
+    intercept: f64,
+    directions: [[f64; 3]; 3],
+    origin: [f64; 3],
+}
+
+impl NiftiHeader {
+    fn parse(bytes: &[u8]) -> io::Result<NiftiHeader> {
+        if bytes.len() < 4 {
+            return Err(invalid("not a NIfTI file"));
+        }
+        // the size of the header gives the version and the byte order
+        let size = i32::from_le_bytes(bytes[..4].try_into().unwrap());
+        let (version, byte_order) = match (size, size.swap_bytes()) {
+            (348, _) => (1, ByteOrder::LittleEndian),
+            (_, 348) => (1, ByteOrder::BigEndian),
+            (540, _) => (2, ByteOrder::LittleEndian),
+            (_, 540) => (2, ByteOrder::BigEndian),
+            _ => return Err(invalid("not a NIfTI file")),
+        };
+        let size = if version == 1 { 348 } else { 540 };
+        if bytes.len() < size {
+            return Err(invalid("truncated NIfTI header"));
+        }
+        let h = Fields { bytes, byte_order };
+
+        // the offsets of the fields in the two versions of the header
+        let (magic, single_file) = if version == 1 {
+            (&bytes[344..348], &b"n+1\0"[..])
+        } else {
+            (&bytes[4..12], &b"n+2\0\r\n\x1a\n"[..])
+        };
+        let pair = if version == 1 {
+            &b"ni1\0"[..]
+        } else {
+            &b"ni2\0\r\n\x1a\n"[..]
+        };
+        if magic != single_file && magic != pair {
+            return Err(invalid("invalid NIfTI magic"));
+        }
+
+        let datatype;
+        let mut dim = [0i64; 8];
+        let mut pixdim = [0f64; 8];
+        let vox_offset;
+        let (slope, intercept);
+        let (qform_code, sform_code);
+        let mut quatern = [0f64; 6];
+        let mut srow = [[0f64; 4]; 3];
+        if version == 1 {
+            datatype = h.i16(70);
+            for i in 0..8 {
+                dim[i] = h.i16(40 + 2 * i).into();
+                pixdim[i] = h.f32(76 + 4 * i).into();
+            }
+            vox_offset = h.f32(108) as i64;
+            (slope, intercept) = (h.f32(112).into(), h.f32(116).into());
+            (qform_code, sform_code) = (h.i16(252).into(), h.i16(254).into());
+            for (i, q) in quatern.iter_mut().enumerate() {
+                *q = h.f32(256 + 4 * i).into();
+            }
+            for (r, row) in srow.iter_mut().enumerate() {
+                for (c, x) in row.iter_mut().enumerate() {
+                    *x = h.f32(280 + 16 * r + 4 * c).into();
+                }
+            }
+        } else {
+            datatype = h.i16(12);
+            for i in 0..8 {
+                dim[i] = h.i64(16 + 8 * i);
+                pixdim[i] = h.f64(104 + 8 * i);
+            }
+            vox_offset = h.i64(168);
+            (slope, intercept) = (h.f64(176), h.f64(184));
+            (qform_code, sform_code) = (h.i32(344), h.i32(348));
+            for (i, q) in quatern.iter_mut().enumerate() {
+                *q = h.f64(352 + 8 * i);
+            }
+            for (r, row) in srow.iter_mut().enumerate() {
+                for (c, x) in row.iter_mut().enumerate() {
+                    *x = h.f64(400 + 32 * r + 8 * c);
+                }
+            }
+        }
+
+        let ty = match datatype {
+            2 => SampleType::U8,
+            4 => SampleType::I16,
+            16 => SampleType::F32,
+            64 => SampleType::F64,
+            512 => SampleType::U16,
+            _ => return Err(invalid(format!("unsupported NIfTI datatype {datatype}"))),
+        };
+
+        let ndim = dim[0];
+        if !(1..=7).contains(&ndim) || (4..=ndim as usize).any(|a| dim[a] != 1) {
+            return Err(invalid("only 3-dimensional volumes are supported"));
+        }
+        let mut shape = [1; 3];
+        for (a, n) in shape.iter_mut().enumerate().take(ndim as usize) {
+            *n = usize::try_from(dim[a + 1]).map_err(|_| invalid("invalid dimensions"))?;
+        }
+
+        // the voxel sizes, with `qfac` giving the handedness of the qform
+        let spacing = [1, 2, 3].map(|i| if pixdim[i] > 0. { pixdim[i] } else { 1. });
+        let qfac = if pixdim[0] < 0. { -1. } else { 1. };
+
+        let (directions, origin) = if sform_code > 0 {
+            (
+                [0, 1, 2].map(|a| [srow[0][a], srow[1][a], srow[2][a]]),
+                [srow[0][3], srow[1][3], srow[2][3]],
+            )
+        } else if qform_code > 0 {
+            let (b, c, d) = (quatern[0], quatern[1], quatern[2]);
+            // a 180 degree rotation if the quaternion is not normalized
+            let a = 1. - (b * b + c * c + d * d);
+            let (a, b, c, d) = if a < 1e-7 {
+                let s = 1. / (b * b + c * c + d * d).sqrt();
+                (0., b * s, c * s, d * s)
+            } else {
+                (a.sqrt(), b, c, d)
+            };
+            let rotation = [
+                [
+                    a * a + b * b - c * c - d * d,
+                    2. * (b * c - a * d),
+                    2. * (b * d + a * c),
+                ],
+                [
+                    2. * (b * c + a * d),
+                    a * a + c * c - b * b - d * d,
+                    2. * (c * d - a * b),
+                ],
+                [
+                    2. * (b * d - a * c),
+                    2. * (c * d + a * b),
+                    a * a + d * d - c * c - b * b,
+                ],
+            ];
+            let spacing = [spacing[0], spacing[1], qfac * spacing[2]];
+            (
+                [0, 1, 2].map(|a| [0, 1, 2].map(|r| rotation[r][a] * spacing[a])),
+                [quatern[3], quatern[4], quatern[5]],
+            )
+        } else {
+            let s = spacing;
+            ([[s[0], 0., 0.], [0., s[1], 0.], [0., 0., s[2]]], [0.; 3])
+        };
+
+        Ok(NiftiHeader {
+            ty,
+            byte_order,
+            shape,
+            single_file: magic == single_file,
+            vox_offset: usize::try_from(vox_offset).map_err(|_| invalid("invalid vox_offset"))?,
+            slope,
+            intercept,
+            directions,
+            origin,
+        })
+    }
+
+    /// Reads the samples from the file with the image.
+    fn read_volume(&self, bytes: &[u8]) -> io::Result<Volume> {
+        let n = sample_count(self.shape)?;
+        let skip = self.vox_offset as i64;
+        let mut samples = binary_samples(bytes, self.ty, n, Some(self.byte_order), skip)?;
+        if self.slope != 0. && self.slope.is_finite() && (self.slope, self.intercept) != (1., 0.) {
+            samples = samples.scale(self.slope, self.intercept);
+        }
+
+        Ok(Volume {
+            samples,
+            shape: self.shape,
+            directions: self.directions,
+            origin: self.origin,
+        })
+    }
+}
+
+/// Reads a volume in the MetaImage format with the data in the same file, as in a `.mha` file.
+///
+/// Volumes with a single channel of the types `MET_UCHAR`, `MET_USHORT`, `MET_SHORT`,
+/// `MET_FLOAT` and `MET_DOUBLE` with at most 3 dimensions are supported, where compressed data
+/// requires the `flate2` feature. The position of the samples is given by `Offset`,
+/// `ElementSpacing` and `TransformMatrix`, whose rows are the directions of the axes, in the
+/// LPS+ space of ITK.
+pub fn read_metaimage<R: BufRead>(reader: R) -> io::Result<Volume> {
+    let mut reader = reader;
+    let header = MetaImageHeader::read(&mut reader)?;
+    if header.data_file != "LOCAL" {
+        return Err(invalid("separate data file, use `read_metaimage_file`"));
+    }
+    header.read_volume(reader)
+}
+
+/// Reads a volume in the MetaImage format from a file, either with the data in the same file or
+/// with a separate header, as in a `.mhd` file, whose data file is found relative to the header.
+///
+/// See `read_metaimage` for the supported volumes.
+pub fn read_metaimage_file<P: AsRef<Path>>(path: P) -> io::Result<Volume> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = MetaImageHeader::read(&mut reader)?;
+    if header.data_file == "LOCAL" {
+        return header.read_volume(reader);
+    }
+    let data_path = path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(&header.data_file);
+    header.read_volume(BufReader::new(File::open(data_path)?))
+}
+
+/// The fields of a MetaImage header needed to read the volume.
+#[derive(Debug)]
+struct MetaImageHeader {
+    ty: SampleType,
+    shape: [usize; 3],
+    byte_order: ByteOrder,
+    compressed: bool,
+    /// The number of bytes to skip, or -1 if the data is at the end.
+    header_size: i64,
+    directions: [[f64; 3]; 3],
+    origin: [f64; 3],
+    data_file: String,
+}
+
+impl MetaImageHeader {
+    /// Reads the header up to and including the line with `ElementDataFile`.
+    fn read<R: BufRead>(reader: &mut R) -> io::Result<MetaImageHeader> {
+        let mut ty = None;
+        let mut ndims = None;
+        let mut dim_size = None;
+        let mut spacing = None;
+        let mut size = None;
+        let mut matrix = [1., 0., 0., 0., 1., 0., 0., 0., 1.];
+        let mut header = MetaImageHeader {
+            ty: SampleType::U8,
+            shape: [1; 3],
+            byte_order: ByteOrder::LittleEndian,
+            compressed: false,
+            header_size: 0,
+            directions: [[0.; 3]; 3],
+            origin: [0.; 3],
+            data_file: String::new(),
+        };
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(invalid("missing ElementDataFile"));
+            }
+            let l = line.trim();
+            if l.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = l.split_once('=') else {
+                return Err(invalid(format!("invalid header line `{l}`")));
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            let numbers = |n: usize| -> io::Result<Vec<f64>> {
+                let v: Vec<f64> = value
+                    .split_whitespace()
+                    .map(|x| {
+                        x.parse()
+                            .map_err(|_| invalid(format!("invalid {key} `{value}`")))
+                    })
+                    .collect::<io::Result<_>>()?;
+                if v.len() < n {
+                    return Err(invalid(format!("invalid {key} `{value}`")));
+                }
+                Ok(v)
+            };
+            let boolean = || value.eq_ignore_ascii_case("true");
+
+            match key {
+                "ObjectType" if value != "Image" => {
+                    return Err(invalid(format!("unsupported ObjectType `{value}`")))
+                }
+                "NDims" => ndims = Some(numbers(1)?[0] as usize),
+                "DimSize" => dim_size = Some(numbers(1)?),
+                "ElementType" => ty = Some(met_type(value)?),
+                "ElementNumberOfChannels" if value != "1" => {
+                    return Err(invalid("only single-channel images are supported"))
+                }
+                "ElementSpacing" => spacing = Some(numbers(1)?),
+                "ElementSize" => size = Some(numbers(1)?),
+                "Offset" | "Origin" | "Position" => {
+                    for (o, x) in header.origin.iter_mut().zip(numbers(1)?) {
+                        *o = x;
+                    }
+                }
+                "TransformMatrix" | "Rotation" | "Orientation" => {
+                    let v = numbers(1)?;
+                    let nd = (v.len() as f64).sqrt() as usize;
+                    if nd * nd != v.len() || nd > 3 {
+                        return Err(invalid(format!("invalid {key} `{value}`")));
+                    }
+                    for r in 0..nd {
+                        for c in 0..nd {
+                            matrix[3 * r + c] = v[nd * r + c];
+                        }
+                    }
+                }
+                "BinaryDataByteOrderMSB" | "ElementByteOrderMSB" => {
+                    header.byte_order = if boolean() {
+                        ByteOrder::BigEndian
+                    } else {
+                        ByteOrder::LittleEndian
+                    }
+                }
+                "CompressedData" => header.compressed = boolean(),
+                "HeaderSize" => header.header_size = numbers(1)?[0] as i64,
+                "ElementDataFile" => {
+                    if value.starts_with("LIST") || value.contains('%') {
+                        return Err(invalid("multiple data files are not supported"));
+                    }
+                    header.data_file = value.to_string();
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let ndims = ndims.ok_or_else(|| invalid("missing NDims"))?;
+        if !(1..=3).contains(&ndims) {
+            return Err(invalid("only 3-dimensional volumes are supported"));
+        }
+        let dim_size = dim_size.ok_or_else(|| invalid("missing DimSize"))?;
+        for (a, n) in header.shape.iter_mut().enumerate().take(ndims) {
+            let size = *dim_size.get(a).ok_or_else(|| invalid("invalid DimSize"))?;
+            if !(size >= 0. && size.fract() == 0. && size < usize::MAX as f64) {
+                return Err(invalid("invalid DimSize"));
+            }
+            *n = size as usize;
+        }
+        header.ty = ty.ok_or_else(|| invalid("missing ElementType"))?;
+        if header.header_size < -1 || (header.header_size == -1 && header.compressed) {
+            return Err(invalid("invalid HeaderSize"));
+        }
+
+        let spacing = spacing.or(size).unwrap_or_default();
+        for a in 0..3 {
+            let s = spacing.get(a).cloned().unwrap_or(1.);
+            header.directions[a] = [0, 1, 2].map(|c| s * matrix[3 * a + c]);
+        }
+        Ok(header)
+    }
+
+    /// Reads the samples from the data following the header.
+    fn read_volume<R: BufRead>(&self, reader: R) -> io::Result<Volume> {
+        let mut reader = reader;
+
+        // a header size applies to a separate data file only
+        let skip = if self.data_file == "LOCAL" {
+            0
+        } else {
+            self.header_size
+        };
+        let n = sample_count(self.shape)?;
+
+        // uncompressed data is read up to its end, data at the end of the file needs all of it
+        let mut bytes = Vec::new();
+        match u64::try_from(skip) {
+            Ok(skip) if !self.compressed => {
+                let len = skip.saturating_add(byte_len(n, self.ty)? as u64);
+                reader.take(len).read_to_end(&mut bytes)?
+            }
+            _ => reader.read_to_end(&mut bytes)?,
+        };
+        let samples = if self.compressed {
+            let compressed = bytes
+                .get(skip as usize..)
+                .ok_or_else(|| invalid("no data"))?;
+            let mut bytes = Vec::new();
+            decompress(compressed, Compression::Zlib, &mut bytes)?;
+            binary_samples(&bytes, self.ty, n, Some(self.byte_order), 0)?
+        } else {
+            binary_samples(&bytes, self.ty, n, Some(self.byte_order), skip)?
+        };
+
+        Ok(Volume {
+            samples,
+            shape: self.shape,
+            directions: self.directions,
+            origin: self.origin,
+        })
+    }
+}
+
+/// Parses a MetaImage element type.
+fn met_type(name: &str) -> io::Result<SampleType> {
+    Ok(match name {
+        "MET_UCHAR" => SampleType::U8,
+        "MET_USHORT" => SampleType::U16,
+        "MET_SHORT" => SampleType::I16,
+        "MET_FLOAT" => SampleType::F32,
+        "MET_DOUBLE" => SampleType::F64,
+        _ => return Err(invalid(format!("unsupported ElementType `{name}`"))),
+    })
+}
+
+/// Parses a NRRD type name.
+fn sample_type(name: &str) -> io::Result<SampleType> {
+    Ok(match name {
+        "uchar" | "unsigned char" | "uint8" | "uint8_t" => SampleType::U8,
+        "ushort" | "unsigned short" | "unsigned short int" | "uint16" | "uint16_t" => {
+            SampleType::U16
+        }
+        "short" | "short int" | "signed short" | "signed short int" | "int16" | "int16_t" => {
+            SampleType::I16
+        }
+        "float" => SampleType::F32,
+        "double" => SampleType::F64,
+        _ => return Err(invalid(format!("unsupported type `{name}`"))),
+    })
+}
+
+/// Parses a NRRD vector such as `(1.5,0,0)`.
+fn vector(s: &str) -> io::Result<[f64; 3]> {
+    let err = || invalid(format!("invalid vector `{s}`"));
+    let inner = s
+        .trim()
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(err)?;
+    let v: Vec<f64> = inner
+        .split(',')
+        .map(|x| x.trim().parse().map_err(|_| err()))
+        .collect::<io::Result<_>>()?;
+    v.try_into().map_err(|_| err())
+}
+
+/// The number of samples of a volume, which must not overflow.
+fn sample_count(shape: [usize; 3]) -> io::Result<usize> {
+    shape
+        .iter()
+        .try_fold(1usize, |n, &s| n.checked_mul(s))
+        .ok_or_else(|| invalid("volume too large"))
+}
+
+/// The size of `n` samples in bytes, which must not overflow.
+fn byte_len(n: usize, ty: SampleType) -> io::Result<usize> {
+    n.checked_mul(ty.size())
+        .ok_or_else(|| invalid("volume too large"))
+}
+
+/// Decodes `n` binary samples at `skip` bytes from the start of `bytes`, or at its end if `skip`
+/// is -1.
+///
+/// The byte order is only required for samples with more than one byte.
+fn binary_samples(
+    bytes: &[u8],
+    ty: SampleType,
+    n: usize,
+    byte_order: Option<ByteOrder>,
+    skip: i64,
+) -> io::Result<Samples> {
+    let len = byte_len(n, ty)?;
+    let start = if skip == -1 {
+        bytes.len().checked_sub(len)
+    } else {
+        usize::try_from(skip)
+            .ok()
+            .filter(|&s| s.checked_add(len).is_some_and(|end| end <= bytes.len()))
+    };
+    let start = start.ok_or_else(|| invalid("not enough data"))?;
+    let byte_order = match (ty, byte_order) {
+        (SampleType::U8, _) => ByteOrder::LittleEndian,
+        (_, Some(byte_order)) => byte_order,
+        (_, None) => return Err(invalid("missing byte order")),
+    };
+    Ok(Samples::from_bytes(
+        &bytes[start..start + len],
+        ty,
+        byte_order,
+    ))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Compression {
+    Gzip,
+    Zlib,
+    /// Raw deflate data, as in ZIP archives.
+    Deflate,
+}
+
+/// Decompresses the data of `reader` into `bytes`.
+#[cfg(feature = "flate2")]
+pub(crate) fn decompress<R: Read>(
+    reader: R,
+    compression: Compression,
+    bytes: &mut Vec<u8>,
+) -> io::Result<()> {
+    match compression {
+        Compression::Gzip => flate2::read::MultiGzDecoder::new(reader).read_to_end(bytes)?,
+        Compression::Zlib => flate2::read::ZlibDecoder::new(reader).read_to_end(bytes)?,
+        Compression::Deflate => flate2::read::DeflateDecoder::new(reader).read_to_end(bytes)?,
+    };
+    Ok(())
+}
+
+#[cfg(not(feature = "flate2"))]
+pub(crate) fn decompress<R: Read>(
+    _reader: R,
+    _: Compression,
+    _bytes: &mut Vec<u8>,
+) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "compressed data requires the `flate2` feature",
+    ))
+}
@@ -0,0 +1,473 @@
+//! VTK output, in the legacy format (`.vtk`) and as XML PolyData (`.vtp`).
+
+use crate::attribute::{PlyFormat, PlyProperty, PlyType, PlyValue};
+use crate::isoline::Isoline;
+use crate::mesh::Mesh;
+use std::io::{self, Write};
+
+/// Encoding of a VTK file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VtkFormat {
+    /// Legacy format with ASCII data.
+    LegacyAscii,
+    /// Legacy format with big-endian binary data.
+    LegacyBinary,
+    /// XML format with ASCII data.
+    XmlAscii,
+    /// XML format with base64 encoded binary data.
+    XmlBase64,
+    /// XML format with raw binary data appended at the end of the file.
+    XmlRaw,
+}
+
+/// Options for the VTK writers.
+#[derive(Clone, Copy, Debug)]
+pub struct VtkOptions<'a> {
+    pub format: VtkFormat,
+    /// The name of the point data arrays of the data.
+    ///
+    /// The data is split into scalar arrays as described by `PlyProperty`. In the legacy
+    /// format, whitespace in the names is replaced by underscores.
+    pub data_name: &'a str,
+}
+
+impl<'a> Default for VtkOptions<'a> {
+    fn default() -> Self {
+        VtkOptions {
+            format: VtkFormat::XmlBase64,
+            data_name: "data",
+        }
+    }
+}
+
+/// Writes a mesh as VTK PolyData with polygons.
+///
+/// The normals are scaled to unit length and written as the point data array `Normals`,
+/// followed by the data arrays.
+pub fn write_vtk<W, D, T>(writer: W, mesh: &Mesh<D, T>, options: &VtkOptions) -> io::Result<()>
+where
+    W: Write,
+    D: PlyProperty + Copy + Into<f64>,
+    T: PlyProperty,
+{
+    let mut points = Vec::with_capacity(3 * mesh.verts.len());
+    mesh.verts
+        .iter()
+        .for_each(|v| v.iter().for_each(|x| x.values(&mut points)));
+
+    let normals = mesh
+        .normals
+        .iter()
+        .flat_map(|n| {
+            let n = n.map(|x| x.into());
+            let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            if len > 0. {
+                n.map(|x| x / len)
+            } else {
+                n
+            }
+        })
+        .map(PlyValue::Double)
+        .collect();
+
+    let poly_data = PolyData {
+        points: DataArray {
+            name: "Points".to_string(),
+            ty: scalar_type::<D>(),
+            components: 3,
+            values: points,
+        },
+        normals: Some(DataArray {
+            name: "Normals".to_string(),
+            ty: PlyType::Double,
+            components: 3,
+            values: normals,
+        }),
+        data: data_arrays(&mesh.data, options.data_name),
+        cells: Cells::Polys,
+        connectivity: mesh.faces.iter().flatten().cloned().collect(),
+        offsets: (1..=mesh.faces.len() as u32).map(|f| 3 * f).collect(),
+    };
+
+    poly_data.write(writer, options.format)
+}
+
+/// Writes the components of an isoline as VTK PolyData with polylines in the plane `z = 0`.
+pub fn write_vtk_isoline<W, D>(
+    writer: W,
+    isoline: &Isoline<D>,
+    options: &VtkOptions,
+) -> io::Result<()>
+where
+    W: Write,
+    D: PlyProperty + Copy + Default,
+{
+    let mut points = Vec::new();
+    let mut offsets = Vec::new();
+    let mut n = 0;
+    for c in isoline.components() {
+        for v in c {
+            v[0].values(&mut points);
+            v[1].values(&mut points);
+            D::default().values(&mut points);
+        }
+        n += c.len() as u32;
+        offsets.push(n);
+    }
+
+    let poly_data = PolyData {
+        points: DataArray {
+            name: "Points".to_string(),
+            ty: scalar_type::<D>(),
+            components: 3,
+            values: points,
+        },
+        normals: None,
+        data: Vec::new(),
+        cells: Cells::Lines,
+        connectivity: (0..n).collect(),
+        offsets,
+    };
+
+    poly_data.write(writer, options.format)
+}
+
+/// The type of the single property of a scalar.
+fn scalar_type<D: PlyProperty>() -> PlyType {
+    let mut props = Vec::new();
+    D::describe("", &mut props);
+    assert_eq!(props.len(), 1, "coordinates must be scalars");
+    props[0].1
+}
+
+/// A scalar array for every property of the data.
+fn data_arrays<T: PlyProperty>(data: &[T], name: &str) -> Vec<DataArray> {
+    let mut props = Vec::new();
+    T::describe(name, &mut props);
+
+    let mut arrays: Vec<DataArray> = props
+        .into_iter()
+        .map(|(name, ty)| DataArray {
+            name,
+            ty,
+            components: 1,
+            values: Vec::with_capacity(data.len()),
+        })
+        .collect();
+
+    let mut values = Vec::with_capacity(arrays.len());
+    for d in data {
+        values.clear();
+        d.values(&mut values);
+        for (a, &v) in arrays.iter_mut().zip(&values) {
+            a.values.push(v);
+        }
+    }
+    arrays
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cells {
+    Lines,
+    Polys,
+}
+
+struct DataArray {
+    name: String,
+    ty: PlyType,
+    components: usize,
+    values: Vec<PlyValue>,
+}
+
+/// Points with point data and cells of a single kind.
+struct PolyData {
+    points: DataArray,
+    normals: Option<DataArray>,
+    data: Vec<DataArray>,
+    cells: Cells,
+    /// The points of all cells.
+    connectivity: Vec<u32>,
+    /// The end of every cell in `connectivity`.
+    offsets: Vec<u32>,
+}
+
+impl PolyData {
+    fn write<W: Write>(&self, writer: W, format: VtkFormat) -> io::Result<()> {
+        let mut w = io::BufWriter::new(writer);
+        match format {
+            VtkFormat::LegacyAscii => self.write_legacy(&mut w, PlyFormat::Ascii)?,
+            VtkFormat::LegacyBinary => self.write_legacy(&mut w, PlyFormat::BinaryBigEndian)?,
+            VtkFormat::XmlAscii | VtkFormat::XmlBase64 | VtkFormat::XmlRaw => {
+                self.write_xml(&mut w, format)?
+            }
+        }
+        w.flush()
+    }
+
+    fn write_legacy<W: Write>(&self, w: &mut W, format: PlyFormat) -> io::Result<()> {
+        let n_points = self.points.values.len() / 3;
+
+        writeln!(w, "# vtk DataFile Version 3.0")?;
+        writeln!(w, "isosurface")?;
+        writeln!(
+            w,
+            "{}",
+            if format == PlyFormat::Ascii {
+                "ASCII"
+            } else {
+                "BINARY"
+            }
+        )?;
+        writeln!(w, "DATASET POLYDATA")?;
+
+        writeln!(w, "POINTS {} {}", n_points, legacy_type(self.points.ty))?;
+        write_values(w, &self.points.values, 3, format)?;
+
+        let keyword = match self.cells {
+            Cells::Lines => "LINES",
+            Cells::Polys => "POLYGONS",
+        };
+        // every cell is written as the number of its points followed by the points
+        let mut cells: Vec<Vec<PlyValue>> = Vec::with_capacity(self.offsets.len());
+        let mut start = 0;
+        for &end in &self.offsets {
+            let mut cell = vec![PlyValue::Int((end - start) as i32)];
+            cell.extend(
+                self.connectivity[start as usize..end as usize]
+                    .iter()
+                    .map(|&p| PlyValue::Int(p as i32)),
+            );
+            cells.push(cell);
+            start = end;
+        }
+        let size: usize = cells.iter().map(|c| c.len()).sum();
+        writeln!(w, "{} {} {}", keyword, cells.len(), size)?;
+        if format == PlyFormat::Ascii {
+            for c in &cells {
+                write_values(w, c, c.len(), format)?;
+            }
+        } else {
+            write_values(w, &cells.concat(), size, format)?;
+        }
+
+        if self.normals.is_some() || !self.data.is_empty() {
+            writeln!(w, "POINT_DATA {}", n_points)?;
+        }
+        if let Some(normals) = &self.normals {
+            writeln!(w, "NORMALS {} {}", normals.name, legacy_type(normals.ty))?;
+            write_values(w, &normals.values, 3, format)?;
+        }
+        for a in &self.data {
+            // the name is a single token in the legacy format
+            let name = a.name.replace(char::is_whitespace, "_");
+            writeln!(w, "SCALARS {} {} 1", name, legacy_type(a.ty))?;
+            writeln!(w, "LOOKUP_TABLE default")?;
+            write_values(w, &a.values, 1, format)?;
+        }
+        Ok(())
+    }
+
+    fn write_xml<W: Write>(&self, w: &mut W, format: VtkFormat) -> io::Result<()> {
+        let n_points = self.points.values.len() / 3;
+        let n_cells = self.offsets.len();
+        let (n_lines, n_polys) = match self.cells {
+            Cells::Lines => (n_cells, 0),
+            Cells::Polys => (0, n_cells),
+        };
+
+        let connectivity = DataArray {
+            name: "connectivity".to_string(),
+            ty: PlyType::Int,
+            components: 1,
+            values: self
+                .connectivity
+                .iter()
+                .map(|&p| PlyValue::Int(p as i32))
+                .collect(),
+        };
+        let offsets = DataArray {
+            name: "offsets".to_string(),
+            ty: PlyType::Int,
+            components: 1,
+            values: self
+                .offsets
+                .iter()
+                .map(|&p| PlyValue::Int(p as i32))
+                .collect(),
+        };
+
+        // the binary data of the arrays in the appended section
+        let mut appended = Vec::new();
+
+        writeln!(w, r#"<?xml version="1.0"?>"#)?;
+        writeln!(
+            w,
+            concat!(
+                r#"<VTKFile type="PolyData" version="1.0" byte_order="LittleEndian""#,
+                r#" header_type="UInt64">"#
+            )
+        )?;
+        writeln!(w, "<PolyData>")?;
+        writeln!(
+            w,
+            concat!(
+                r#"<Piece NumberOfPoints="{}" NumberOfVerts="0" NumberOfLines="{}""#,
+                r#" NumberOfStrips="0" NumberOfPolys="{}">"#
+            ),
+            n_points, n_lines, n_polys
+        )?;
+
+        match &self.normals {
+            Some(normals) => writeln!(w, r#"<PointData Normals="{}">"#, xml_escape(&normals.name))?,
+            None => writeln!(w, "<PointData>")?,
+        }
+        for a in self.normals.iter().chain(&self.data) {
+            write_data_array(w, a, format, &mut appended)?;
+        }
+        writeln!(w, "</PointData>")?;
+
+        writeln!(w, "<Points>")?;
+        write_data_array(w, &self.points, format, &mut appended)?;
+        writeln!(w, "</Points>")?;
+
+        let tag = match self.cells {
+            Cells::Lines => "Lines",
+            Cells::Polys => "Polys",
+        };
+        writeln!(w, "<{tag}>")?;
+        write_data_array(w, &connectivity, format, &mut appended)?;
+        write_data_array(w, &offsets, format, &mut appended)?;
+        writeln!(w, "</{tag}>")?;
+
+        writeln!(w, "</Piece>")?;
+        writeln!(w, "</PolyData>")?;
+        if format == VtkFormat::XmlRaw {
+            write!(w, r#"<AppendedData encoding="raw">_"#)?;
+            w.write_all(&appended)?;
+            writeln!(w, "\n</AppendedData>")?;
+        }
+        writeln!(w, "</VTKFile>")
+    }
+}
+
+/// Writes the values with `n` values on every line in the ASCII format.
+fn write_values<W: Write>(
+    w: &mut W,
+    values: &[PlyValue],
+    n: usize,
+    format: PlyFormat,
+) -> io::Result<()> {
+    for row in values.chunks(n) {
+        for (i, v) in row.iter().enumerate() {
+            if format == PlyFormat::Ascii && i > 0 {
+                w.write_all(b" ")?;
+            }
+            v.write(w, format)?;
+        }
+        if format == PlyFormat::Ascii {
+            w.write_all(b"\n")?;
+        }
+    }
+    if format != PlyFormat::Ascii {
+        w.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Writes a `DataArray` element, with the binary data appended to `appended` in the raw
+/// format.
+fn write_data_array<W: Write>(
+    w: &mut W,
+    a: &DataArray,
+    format: VtkFormat,
+    appended: &mut Vec<u8>,
+) -> io::Result<()> {
+    write!(
+        w,
+        r#"<DataArray type="{}" Name="{}" NumberOfComponents="{}""#,
+        xml_type(a.ty),
+        xml_escape(&a.name),
+        a.components
+    )?;
+
+    // the binary data is preceded by its length
+    let binary = || -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        for v in &a.values {
+            v.write(&mut data, PlyFormat::BinaryLittleEndian)?;
+        }
+        let mut block = (data.len() as u64).to_le_bytes().to_vec();
+        block.append(&mut data);
+        Ok(block)
+    };
+
+    match format {
+        VtkFormat::XmlAscii => {
+            writeln!(w, r#" format="ascii">"#)?;
+            write_values(w, &a.values, a.components.max(1), PlyFormat::Ascii)?;
+            writeln!(w, "</DataArray>")
+        }
+        VtkFormat::XmlBase64 => {
+            writeln!(w, r#" format="binary">"#)?;
+            writeln!(w, "{}", base64(&binary()?))?;
+            writeln!(w, "</DataArray>")
+        }
+        _ => {
+            writeln!(w, r#" format="appended" offset="{}"/>"#, appended.len())?;
+            appended.append(&mut binary()?);
+            Ok(())
+        }
+    }
+}
+
+fn legacy_type(ty: PlyType) -> &'static str {
+    match ty {
+        PlyType::Char => "char",
+        PlyType::UChar => "unsigned_char",
+        PlyType::Short => "short",
+        PlyType::UShort => "unsigned_short",
+        PlyType::Int => "int",
+        PlyType::UInt => "unsigned_int",
+        PlyType::Float => "float",
+        PlyType::Double => "double",
+    }
+}
+
+fn xml_type(ty: PlyType) -> &'static str {
+    match ty {
+        PlyType::Char => "Int8",
+        PlyType::UChar => "UInt8",
+        PlyType::Short => "Int16",
+        PlyType::UShort => "UInt16",
+        PlyType::Int => "Int32",
+        PlyType::UInt => "UInt32",
+        PlyType::Float => "Float32",
+        PlyType::Double => "Float64",
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Standard base64 encoding with padding.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut r = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [0, 1, 2].map(|i| chunk.get(i).cloned().unwrap_or(0) as u32);
+        let n = b[0] << 16 | b[1] << 8 | b[2];
+        for i in 0..4 {
+            if i <= chunk.len() {
+                r.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                r.push('=');
+            }
+        }
+    }
+    r
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "ndarray")]
+
+extern crate isosurface;
+extern crate ndarray;
+
+use isosurface::{
+    marching_tetrahedra_array, marching_tetrahedra_with_options, marching_triangles_array,
+    marching_triangles_with_options, IsolineOptions, Options,
+};
+use ndarray::{s, Array, ArrayView3};
+
+/// The slice-based driver on a copy of `u` in _row-major order_.
+fn expected(u: ArrayView3<f64>, level: f64) -> isosurface::Mesh<f64> {
+    let dim = u.dim();
+    let values: Vec<f64> = u.iter().cloned().collect();
+    let data = vec![(); values.len()];
+    marching_tetrahedra_with_options(&values, dim, level, &data, &Options::default())
+}
+
+#[test]
+fn memory_layouts() {
+    let (ni, nj, nk) = (7, 8, 9);
+    let u = Array::from_shape_fn((ni, nj, nk), |(i, j, k)| {
+        let (x, y, z) = (i as f64 - 2.5, j as f64 - 4., 0.8 * k as f64 - 3.);
+        (x * x + y * y + z * z).sqrt()
+    });
+    let level = 2.7;
+
+    let views = [
+        u.view(),
+        // column-major order
+        u.view().reversed_axes(),
+        // a negative stride
+        u.slice(s![..;-1, .., ..]),
+        // a negative stride and a step
+        u.slice(s![.., ..;-2, 1..]),
+    ];
+    assert!(views[1..].iter().all(|v| !v.is_standard_layout()));
+    for view in views {
+        let data = Array::from_elem(view.dim(), ());
+        let mesh = marching_tetrahedra_array(view, level, data.view(), &Options::default());
+        assert!(!mesh.is_empty());
+        assert_eq!(mesh, expected(view, level));
+    }
+}
+
+#[test]
+fn memory_layouts_2d() {
+    let u = Array::from_shape_fn((7, 9), |(i, j)| {
+        let (x, y) = (i as f64 - 2.5, 0.7 * j as f64 - 3.);
+        (x * x + y * y).sqrt()
+    });
+
+    for view in [u.view(), u.view().reversed_axes(), u.slice(s![..;-1, ..])] {
+        let values: Vec<f64> = view.iter().cloned().collect();
+        let options = IsolineOptions::default();
+        let isoline = marching_triangles_array(view, 2.2, &options);
+        let expected = marching_triangles_with_options(&values, view.dim(), 2.2, &options);
+        // the closed curves are traced from an arbitrary start, which is repeated at the end
+        let sorted = |isoline: &isosurface::Isoline<f64>| {
+            let mut verts: Vec<[u64; 2]> = isoline
+                .components()
+                .flatten()
+                .map(|p| p.map(f64::to_bits))
+                .collect();
+            verts.sort_unstable();
+            verts.dedup();
+            verts
+        };
+        assert_eq!(isoline.components().len(), 1);
+        assert_eq!(sorted(&isoline), sorted(&expected));
+    }
+}
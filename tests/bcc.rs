@@ -0,0 +1,66 @@
+extern crate isosurface;
+
+use isosurface::{marching_tetrahedra_bcc, marching_tetrahedra_bcc_resampled, validate_mesh};
+
+/// Values of `f` at the nodes of an `n`³ grid and at the centers of its cells.
+fn sample(n: usize, f: impl Fn([f64; 3]) -> f64) -> (Vec<f64>, Vec<f64>) {
+    let grid = |m: usize, offset: f64| {
+        let mut u = Vec::with_capacity(m * m * m);
+        for i in 0..m {
+            for j in 0..m {
+                for k in 0..m {
+                    u.push(f([i as f64 + offset, j as f64 + offset, k as f64 + offset]));
+                }
+            }
+        }
+        u
+    };
+    (grid(n, 0.), grid(n - 1, 0.5))
+}
+
+#[test]
+fn sphere_is_closed() {
+    let n = 10;
+    let c = (n as f64 - 1.) / 2.;
+    let (u, centers) = sample(n, |x| {
+        ((x[0] - c).powi(2) + (x[1] - c).powi(2) + (x[2] - c).powi(2)).sqrt() - 3.3
+    });
+    let data = vec![(); u.len()];
+    let center_data = vec![(); centers.len()];
+
+    let meshes = [
+        marching_tetrahedra_bcc(&u, &centers, (n, n, n), 0., &data, &center_data),
+        marching_tetrahedra_bcc_resampled(&u, (n, n, n), 0., &data),
+    ];
+    for mesh in meshes {
+        assert!(!mesh.faces.is_empty());
+        // `validate_mesh` welds the separate vertices of the tetrahedra by position
+        let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+        assert!(report.duplicate_vertices > 0);
+        assert!(report.is_watertight(), "{:?}", report);
+        assert!(report.is_oriented(), "{:?}", report);
+    }
+}
+
+#[test]
+fn plane_vertices_are_on_the_plane() {
+    let n = 6;
+    let plane = |x: [f64; 3]| x[0] + 2. * x[1] - 0.5 * x[2] - 3.1;
+    let (u, centers) = sample(n, plane);
+    let data = vec![(); u.len()];
+    let center_data = vec![(); centers.len()];
+
+    let meshes = [
+        marching_tetrahedra_bcc(&u, &centers, (n, n, n), 0., &data, &center_data),
+        // the averages of a linear function are exact
+        marching_tetrahedra_bcc_resampled(&u, (n, n, n), 0., &data),
+    ];
+    for mesh in meshes {
+        assert!(!mesh.faces.is_empty());
+        for &v in &mesh.verts {
+            assert!(plane(v).abs() < 1e-12, "{:?}", v);
+        }
+        let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+        assert!(report.is_manifold() && report.is_oriented(), "{:?}", report);
+    }
+}
@@ -0,0 +1,83 @@
+extern crate isosurface;
+
+use isosurface::{write_glb, GltfOptions, Mesh};
+
+/// The JSON and the binary chunk of a `.glb` file.
+fn chunks(glb: &[u8]) -> (String, &[u8]) {
+    let u32_at = |i: usize| u32::from_le_bytes(glb[i..i + 4].try_into().unwrap()) as usize;
+    assert_eq!(&glb[..4], b"glTF");
+    assert_eq!(u32_at(8), glb.len());
+    let json_len = u32_at(12);
+    let json = String::from_utf8(glb[20..20 + json_len].to_vec()).unwrap();
+    let bin = &glb[20 + json_len + 8..];
+    (json, bin)
+}
+
+/// Two triangles, with a zero normal, a normal that is not normalized, a NaN normal, and a
+/// position that is not finite.
+fn mesh() -> Mesh<f64> {
+    let mut mesh = Mesh::new();
+    mesh.verts = vec![
+        [0., 0., 0.],
+        [1., 0., 0.],
+        [0., 1., 0.],
+        [0., 0., 0.],
+        [0., 2., 0.],
+        [0., 0., 2.],
+        [f64::INFINITY, 0., 0.],
+    ];
+    mesh.normals = vec![
+        [0., 0., 0.],
+        [0., 0., 3.],
+        [f64::NAN, 0., 0.],
+        [0., 0., 0.],
+        [1., 0., 0.],
+        [1., 0., 0.],
+        [0., 0., 0.],
+    ];
+    mesh.data = vec![(); 7];
+    mesh.faces = vec![[0, 1, 2], [3, 4, 5]];
+    mesh
+}
+
+#[test]
+fn unit_normals() {
+    let mesh = mesh();
+    let mut out = Vec::new();
+    write_glb(&mut out, &[("mesh", &mesh)], &GltfOptions::default()).unwrap();
+    let (_, bin) = chunks(&out);
+
+    // the normals follow the positions
+    let floats: Vec<f32> = bin
+        .chunks(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    let normals: Vec<&[f32]> = floats[3 * 7..6 * 7].chunks(3).collect();
+    assert_eq!(
+        normals,
+        [
+            // the faces
+            &[0., 0., 1.][..],
+            &[0., 0., 1.],
+            &[0., 0., 1.],
+            &[1., 0., 0.],
+            &[1., 0., 0.],
+            &[1., 0., 0.],
+            // not on any face
+            &[0., 0., 1.],
+        ]
+    );
+}
+
+#[test]
+fn finite_bounds() {
+    let mesh = mesh();
+    let mut out = Vec::new();
+    write_glb(&mut out, &[("mesh", &mesh)], &GltfOptions::default()).unwrap();
+    let (json, _) = chunks(&out);
+
+    assert!(json.contains(r#""min":[0,0,0],"max":[1,2,2]"#), "{}", json);
+    for word in ["NaN", "inf"] {
+        assert!(!json.contains(word), "{}", json);
+    }
+}
@@ -0,0 +1,117 @@
+//! The drivers writing meshes in the vector types of other crates; run with
+//! `--features nalgebra,glam,mint`.
+
+#![cfg(any(feature = "nalgebra", feature = "glam", feature = "mint"))]
+
+extern crate isosurface;
+
+use isosurface::{
+    marching_tetrahedra_sink, marching_tetrahedra_welded_sink, Interpolate, Mesh, MeshSink,
+    Options, Scalar, StridedView, VectorMesh,
+};
+
+const N: usize = 8;
+
+/// A sphere, with the positions of the nodes as their data.
+fn sphere<D: Scalar, T>(position: impl Fn([D; 3]) -> T) -> (Vec<D>, Vec<T>) {
+    let c = (N as f32 - 1.) / 2.;
+    let mut u = Vec::new();
+    let mut data = Vec::new();
+    for i in 0..N {
+        for j in 0..N {
+            for k in 0..N {
+                let x = [i, j, k].map(|i| i as f32 - c);
+                u.push(D::from((x[0] * x[0] + x[1] * x[1] + x[2] * x[2]).sqrt()));
+                data.push(position([i, j, k].map(|i| D::from(i as f32))));
+            }
+        }
+    }
+    (u, data)
+}
+
+/// Runs the driver, once into `sink` and once into a `Mesh`.
+fn extract<D, T, K>(u: &[D], data: &[T], welded: bool, sink: &mut K) -> Mesh<D, T>
+where
+    D: Scalar,
+    T: Interpolate<D> + Copy,
+    K: MeshSink<D, T>,
+{
+    let u = StridedView::row_major(u, [N; 3]);
+    let data = StridedView::row_major(data, [N; 3]);
+    let level = D::from(2.5);
+    let options = Options::default();
+
+    let mut mesh = Mesh::new();
+    if welded {
+        marching_tetrahedra_welded_sink(u, level, data, &options, sink);
+        marching_tetrahedra_welded_sink(u, level, data, &options, &mut mesh);
+    } else {
+        marching_tetrahedra_sink(u, level, data, &options, sink);
+        marching_tetrahedra_sink(u, level, data, &options, &mut mesh);
+    }
+    assert!(!mesh.is_empty());
+    mesh
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn nalgebra_mesh() {
+    use nalgebra::{Point3, Vector3};
+
+    let (u, data) = sphere(Point3::<f64>::from);
+    for welded in [false, true] {
+        let mut out: VectorMesh<Point3<f64>, Vector3<f64>, Point3<f64>> = VectorMesh::new();
+        let mesh = extract(&u, &data, welded, &mut out);
+
+        assert_eq!(out.faces, mesh.faces);
+        for (i, p) in out.verts.iter().enumerate() {
+            assert_eq!(*p, Point3::from(mesh.verts[i]));
+            assert_eq!(out.normals[i], Vector3::from(mesh.normals[i]));
+            // the positions are linear, so they are interpolated exactly
+            assert!((out.data[i] - p).norm() < 1e-12);
+        }
+    }
+}
+
+#[cfg(feature = "glam")]
+#[test]
+fn glam_mesh() {
+    use glam::{DVec3, Vec3};
+
+    let (u, data) = sphere(Vec3::from);
+    for welded in [false, true] {
+        let mut out: VectorMesh<Vec3, Vec3, Vec3> = VectorMesh::new();
+        let mesh = extract(&u, &data, welded, &mut out);
+
+        assert_eq!(out.faces, mesh.faces);
+        for (i, p) in out.verts.iter().enumerate() {
+            assert_eq!(*p, Vec3::from(mesh.verts[i]));
+            assert!((out.data[i] - *p).length() < 1e-5);
+        }
+    }
+
+    let (u, data) = sphere(DVec3::from);
+    let mut out: VectorMesh<DVec3, DVec3, DVec3> = VectorMesh::new();
+    let mesh = extract(&u, &data, true, &mut out);
+    assert_eq!(out.verts.len(), mesh.verts.len());
+}
+
+#[cfg(feature = "mint")]
+#[test]
+fn mint_mesh() {
+    use mint::{Point3, Vector3};
+
+    let (u, data) = sphere(Point3::<f32>::from);
+    for welded in [false, true] {
+        let mut out: VectorMesh<Point3<f32>, Vector3<f32>, Point3<f32>> = VectorMesh::new();
+        let mesh = extract(&u, &data, welded, &mut out);
+
+        assert_eq!(out.faces, mesh.faces);
+        for (i, p) in out.verts.iter().enumerate() {
+            assert_eq!(<[f32; 3]>::from(*p), mesh.verts[i]);
+            assert_eq!(<[f32; 3]>::from(out.normals[i]), mesh.normals[i]);
+            let d = out.data[i];
+            assert!((d.x - p.x).abs() + (d.y - p.y).abs() + (d.z - p.z).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+extern crate isosurface;
+
+use isosurface::{
+    marching_triangles_with_options, marching_triangles_with_options_emit, IsolineOptions,
+};
+use std::collections::HashMap;
+
+/// Values of the distance from a circle around the point `(0, 5.5)` on an `n`×`n` grid, periodic
+/// along the first axis, so that the circle crosses the boundary of the grid.
+fn circle(n: usize) -> Vec<f64> {
+    let mut u = Vec::with_capacity(n * n);
+    for i in 0..n {
+        for j in 0..n {
+            let x = i.min(n - i) as f64;
+            let y = j as f64 - 5.5;
+            u.push((x * x + y * y).sqrt() - 3.3);
+        }
+    }
+    u
+}
+
+/// The segments of the isoline, and the number of segments at every endpoint.
+fn emit_segments(
+    u: &[f64],
+    n: usize,
+    options: &IsolineOptions,
+) -> (Vec<[[f64; 2]; 2]>, Vec<usize>) {
+    let data = vec![(); u.len()];
+    let mut segments = Vec::new();
+    marching_triangles_with_options_emit(u, &data, (n, n), 0., options, |s, _| segments.push(s));
+
+    let mut degrees: HashMap<[u64; 2], usize> = HashMap::new();
+    for p in segments.iter().flatten() {
+        *degrees.entry(p.map(|x| (x + 0.).to_bits())).or_default() += 1;
+    }
+    (segments, degrees.into_values().collect())
+}
+
+#[test]
+fn periodic_circle_is_closed() {
+    let n = 12;
+    let u = circle(n);
+    let options = IsolineOptions {
+        periodic: [true, false],
+        ..IsolineOptions::default()
+    };
+
+    let (segments, degrees) = emit_segments(&u, n, &options);
+    assert!(!segments.is_empty());
+    assert!(degrees.iter().all(|&d| d == 2), "{:?}", degrees);
+    for p in segments.iter().flatten() {
+        assert!(p[0] >= 0. && p[0] < n as f64, "{:?}", p);
+    }
+
+    let isoline = marching_triangles_with_options(&u, (n, n), 0., &options);
+    assert_eq!(isoline.components().len(), 1);
+    let c = isoline.components().next().unwrap();
+    assert_eq!(c.first(), c.last());
+
+    // the circle is cut at the boundary of a grid that is not periodic
+    let (_, degrees) = emit_segments(&u, n, &IsolineOptions::default());
+    assert_eq!(degrees.iter().filter(|&&d| d == 1).count(), 4);
+}
+
+#[test]
+fn unwrapped_periodic_circle() {
+    let n = 12;
+    let u = circle(n);
+    let options = IsolineOptions {
+        periodic: [true, false],
+        unwrap: true,
+        ..IsolineOptions::default()
+    };
+
+    // the segments across the boundary end beyond the last node
+    let (segments, _) = emit_segments(&u, n, &options);
+    let max = segments.iter().flatten().map(|p| p[0]).fold(0., f64::max);
+    assert!(max > (n - 1) as f64, "{}", max);
+
+    // the traced curve is continuous, and leaves the fundamental domain on one side
+    let isoline = marching_triangles_with_options(&u, (n, n), 0., &options);
+    assert_eq!(isoline.components().len(), 1);
+    let c = isoline.components().next().unwrap();
+    assert_eq!(c.first(), c.last());
+    for w in c.windows(2) {
+        assert!((w[0][0] - w[1][0]).abs() <= 1., "{:?}", w);
+    }
+    let (min, max) = c
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), p| {
+            (min.min(p[0]), max.max(p[0]))
+        });
+    assert!(min < 0. || max > n as f64, "{} {}", min, max);
+    assert!(max - min > 6., "{} {}", min, max);
+}
+
+#[test]
+fn masked_region_is_empty() {
+    let n = 12;
+    let u = circle(n);
+    let data = vec![(); u.len()];
+    let count = |options: &IsolineOptions| {
+        let mut count = 0;
+        marching_triangles_with_options_emit(&u, &data, (n, n), 0., options, |_, _| count += 1);
+        count
+    };
+
+    let mask = vec![false; u.len()];
+    let options = IsolineOptions {
+        mask: Some(&mask),
+        ..IsolineOptions::default()
+    };
+    assert_eq!(count(&options), 0);
+    let isoline = marching_triangles_with_options(&u, (n, n), 0., &options);
+    assert_eq!(isoline.components().len(), 0);
+
+    // only the second half along the second axis is valid
+    let mask: Vec<bool> = (0..u.len()).map(|i| i % n >= n / 2).collect();
+    let options = IsolineOptions {
+        mask: Some(&mask),
+        ..IsolineOptions::default()
+    };
+    let (segments, degrees) = emit_segments(&u, n, &options);
+    assert!(!segments.is_empty());
+    assert!(segments.iter().flatten().all(|p| p[1] >= (n / 2) as f64));
+    // the two arcs end at the boundary of the valid region
+    assert_eq!(degrees.iter().filter(|&&d| d == 1).count(), 4);
+    let isoline = marching_triangles_with_options(&u, (n, n), 0., &options);
+    assert_eq!(isoline.components().len(), 2);
+    for c in isoline.components() {
+        assert!(c.first() != c.last());
+    }
+}
+
+#[test]
+fn nan_samples_are_skipped() {
+    let n = 12;
+    let mut u = circle(n);
+    // a node just outside the circle
+    u[3 * n + 7] = f64::NAN;
+
+    // NaN compares as below the level, so it is interpolated with the nodes above
+    let (segments, _) = emit_segments(&u, n, &IsolineOptions::default());
+    assert!(segments
+        .iter()
+        .flatten()
+        .any(|p| p[0].is_nan() || p[1].is_nan()));
+
+    let options = IsolineOptions {
+        skip_nan: true,
+        ..IsolineOptions::default()
+    };
+    let (segments, degrees) = emit_segments(&u, n, &options);
+    assert!(!segments.is_empty());
+    assert!(segments
+        .iter()
+        .flatten()
+        .all(|p| !p[0].is_nan() && !p[1].is_nan()));
+    // an arc is cut at the node, besides the two arcs cut at the boundary of the grid
+    assert_eq!(degrees.iter().filter(|&&d| d == 1).count(), 6);
+}
@@ -0,0 +1,353 @@
+extern crate isosurface;
+
+use isosurface::{
+    marching_tetrahedra_iter, marching_tetrahedra_samples, marching_tetrahedra_strided_emit,
+    marching_tetrahedra_welded, marching_tetrahedra_with_options, validate_mesh, Decomposition,
+    Mesh, Options, Scalar, StridedView,
+};
+
+const DECOMPOSITIONS: [Decomposition; 3] = [
+    Decomposition::Six,
+    Decomposition::SixRotated,
+    Decomposition::Five,
+];
+
+/// Values of `f` at the nodes of an `n`³ grid centered at the origin.
+fn sample(n: usize, f: impl Fn([f64; 3]) -> f64) -> Vec<f64> {
+    let c = (n as f64 - 1.) / 2.;
+    let mut u = Vec::with_capacity(n * n * n);
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                u.push(f([i as f64 - c, j as f64 - c, k as f64 - c]));
+            }
+        }
+    }
+    u
+}
+
+fn norm(x: [f64; 3]) -> f64 {
+    (x[0] * x[0] + x[1] * x[1] + x[2] * x[2]).sqrt()
+}
+
+#[test]
+fn welded_mask_at_level_is_manifold() {
+    let n = 12;
+    let u = sample(n, |x| if norm(x) < 4. { 1. } else { 0. });
+    let data = vec![(); u.len()];
+
+    for decomposition in DECOMPOSITIONS {
+        let options = Options {
+            decomposition,
+            ..Options::default()
+        };
+        let mesh = marching_tetrahedra_welded(&u, (n, n, n), 1., &data, &options);
+        let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+        assert!(!mesh.is_empty());
+        assert_eq!(report.duplicate_vertices, 0, "{:?}", decomposition);
+        assert!(report.degenerate_faces.is_empty(), "{:?}", decomposition);
+        assert!(report.is_valid(), "{:?}: {:?}", decomposition, report);
+    }
+}
+
+#[test]
+fn welded_quantized_distance_is_manifold() {
+    // a distance rounded to halves, so many nodes are exactly at the level
+    let n = 12;
+    let u = sample(n, |x| (2. * norm(x)).round() / 2.);
+    let data = vec![(); u.len()];
+
+    for decomposition in DECOMPOSITIONS {
+        let options = Options {
+            decomposition,
+            ..Options::default()
+        };
+        let mesh = marching_tetrahedra_welded(&u, (n, n, n), 3.5, &data, &options);
+        let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+        assert!(report.is_valid(), "{:?}: {:?}", decomposition, report);
+    }
+}
+
+#[test]
+fn welded_snapping_leaves_no_degenerate_faces() {
+    let n = 12;
+    // vertices snapped to the nodes at these radii leave slivers whose area is zero only up to
+    // rounding
+    for radius in [3.3, 4., 4.7] {
+        let u = sample(n, |x| norm(x) - radius);
+        let data = vec![(); u.len()];
+
+        for decomposition in DECOMPOSITIONS {
+            for tol in [0.05, 0.1, 0.2, 0.3, 0.4, 0.5] {
+                let options = Options {
+                    decomposition,
+                    snap: Some(tol),
+                    ..Options::default()
+                };
+                let mesh = marching_tetrahedra_welded(&u, (n, n, n), 0., &data, &options);
+                let report = validate_mesh(&mesh.verts, &mesh.faces, 1e-5);
+                assert!(
+                    report.degenerate_faces.is_empty(),
+                    "radius {}, {:?}, tol {}: {:?}",
+                    radius,
+                    decomposition,
+                    tol,
+                    report.degenerate_faces
+                );
+                assert!(
+                    report.is_valid(),
+                    "radius {}, {:?}, tol {}: {:?}",
+                    radius,
+                    decomposition,
+                    tol,
+                    report
+                );
+            }
+        }
+    }
+}
+
+/// The Schwarz P surface, which is closed only across the boundary of a periodic grid.
+fn schwarz_p(n: usize) -> Vec<f64> {
+    let t = 2. * std::f64::consts::PI / n as f64;
+    let mut u = Vec::with_capacity(n * n * n);
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                u.push((t * i as f64).cos() + (t * j as f64).cos() + (t * k as f64).cos() + 0.1);
+            }
+        }
+    }
+    u
+}
+
+#[test]
+fn periodic_level_set_is_closed() {
+    let n = 8;
+    let u = schwarz_p(n);
+    let data = vec![(); u.len()];
+
+    for decomposition in DECOMPOSITIONS {
+        let options = Options {
+            decomposition,
+            periodic: [true; 3],
+            ..Options::default()
+        };
+        let mesh = marching_tetrahedra_welded(&u, (n, n, n), 0., &data, &options);
+        let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+        assert!(report.is_valid(), "{:?}: {:?}", decomposition, report);
+
+        // the separate vertices of every tetrahedron are merged by their positions
+        let mesh = marching_tetrahedra_with_options(&u, (n, n, n), 0., &data, &options);
+        let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+        assert!(report.is_watertight(), "{:?}: {:?}", decomposition, report);
+        assert!(report.is_oriented(), "{:?}: {:?}", decomposition, report);
+    }
+}
+
+#[test]
+fn unwrapped_periodic_level_set_is_open() {
+    let n = 8;
+    let u = schwarz_p(n);
+    let data = vec![(); u.len()];
+
+    let options = Options {
+        periodic: [true; 3],
+        unwrap: true,
+        ..Options::default()
+    };
+    let mesh = marching_tetrahedra_welded(&u, (n, n, n), 0., &data, &options);
+    let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+    assert!(report.is_manifold());
+    assert!(report.is_oriented());
+    assert!(!report.boundary_edges.is_empty());
+
+    // the boundary lies on the faces of the extended grid
+    let n = n as f64;
+    for e in &report.boundary_edges {
+        for &v in e {
+            let p = mesh.verts[v as usize];
+            assert!(p.iter().any(|&x| x == 0. || x == n), "{:?}", p);
+        }
+    }
+
+    // every coordinate is in [0, n]
+    for p in &mesh.verts {
+        assert!(p.iter().all(|&x| (0. ..=n).contains(&x)), "{:?}", p);
+    }
+}
+
+#[test]
+fn masked_region_is_empty() {
+    let n = 12;
+    let u = sample(n, |x| norm(x) - 3.3);
+    let data = vec![(); u.len()];
+
+    let mask = vec![false; u.len()];
+    let options = Options {
+        mask: Some(&mask),
+        ..Options::default()
+    };
+    assert!(marching_tetrahedra_with_options(&u, (n, n, n), 0., &data, &options).is_empty());
+    assert!(marching_tetrahedra_welded(&u, (n, n, n), 0., &data, &options).is_empty());
+
+    // the nodes in the first half along the first axis are invalid
+    let mask: Vec<bool> = (0..u.len()).map(|i| i / (n * n) >= n / 2).collect();
+    let options = Options {
+        mask: Some(&mask),
+        ..Options::default()
+    };
+    for mesh in [
+        marching_tetrahedra_with_options(&u, (n, n, n), 0., &data, &options),
+        marching_tetrahedra_welded(&u, (n, n, n), 0., &data, &options),
+    ] {
+        assert!(!mesh.is_empty());
+        assert!(mesh.verts.iter().all(|v| v[0] >= (n / 2) as f64));
+        // the hemisphere ends cleanly at the boundary of the valid region
+        let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+        assert!(report.is_manifold() && report.is_oriented(), "{:?}", report);
+        assert!(!report.boundary_edges.is_empty());
+        for [a, b] in report.boundary_edges {
+            assert_eq!(mesh.verts[a as usize][0], (n / 2) as f64);
+            assert_eq!(mesh.verts[b as usize][0], (n / 2) as f64);
+        }
+    }
+}
+
+#[test]
+fn nan_samples_are_skipped() {
+    let n = 12;
+    let mut u = sample(n, |x| norm(x) - 3.3);
+    let data = vec![(); u.len()];
+    let index = |[i, j, k]: [usize; 3]| (i * n + j) * n + k;
+
+    // a node inside the sphere compares as below the level
+    let expected = marching_tetrahedra_with_options(&u, (n, n, n), 0., &data, &Options::default());
+    u[index([5, 5, 5])] = f64::NAN;
+    let mesh = marching_tetrahedra_with_options(&u, (n, n, n), 0., &data, &Options::default());
+    assert_eq!(mesh.verts, expected.verts);
+
+    // a node just outside the sphere is interpolated with the nodes inside
+    u[index([5, 5, 9])] = f64::NAN;
+    let mesh = marching_tetrahedra_with_options(&u, (n, n, n), 0., &data, &Options::default());
+    let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+    assert!(!report.nan_vertices.is_empty());
+
+    let options = Options {
+        skip_nan: true,
+        ..Options::default()
+    };
+    for mesh in [
+        marching_tetrahedra_with_options(&u, (n, n, n), 0., &data, &options),
+        marching_tetrahedra_welded(&u, (n, n, n), 0., &data, &options),
+    ] {
+        let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+        assert!(report.nan_vertices.is_empty(), "{:?}", report);
+        // a hole around the node
+        assert!(report.is_manifold() && report.is_oriented(), "{:?}", report);
+        assert!(!report.boundary_edges.is_empty());
+    }
+}
+
+/// The mesh of integer or half-precision samples `u`, and of the same values converted into
+/// `D` beforehand.
+fn samples_meshes<S, D>(u: &[S], n: usize, level: D) -> [Mesh<D>; 2]
+where
+    S: Into<D> + Copy,
+    D: Scalar,
+{
+    let data = vec![(); u.len()];
+    let data = StridedView::row_major(&data, [n, n, n]);
+    let converted: Vec<D> = u.iter().map(|&x| x.into()).collect();
+    let options = Options::default();
+    [
+        marching_tetrahedra_samples(StridedView::row_major(u, [n, n, n]), level, data, &options),
+        marching_tetrahedra_samples(
+            StridedView::row_major(&converted, [n, n, n]),
+            level,
+            data,
+            &options,
+        ),
+    ]
+}
+
+#[test]
+fn integer_and_half_samples() {
+    let n = 10;
+    let u = sample(n, |x| 1000. * norm(x));
+
+    let u16s: Vec<u16> = u.iter().map(|&x| x as u16).collect();
+    let [mesh, expected] = samples_meshes::<_, f64>(&u16s, n, 3300.5);
+    assert!(!mesh.is_empty());
+    assert_eq!(mesh, expected);
+    assert!(validate_mesh(&mesh.verts, &mesh.faces, 0.).is_valid());
+
+    let i16s: Vec<i16> = u.iter().map(|&x| (x - 3000.) as i16).collect();
+    let [mesh, expected] = samples_meshes::<_, f64>(&i16s, n, 300.5);
+    assert!(!mesh.is_empty());
+    assert_eq!(mesh, expected);
+
+    let u8s: Vec<u8> = u.iter().map(|&x| (x / 40.) as u8).collect();
+    let [mesh, expected] = samples_meshes::<_, f32>(&u8s, n, 82.5);
+    assert!(!mesh.is_empty());
+    assert_eq!(mesh, expected);
+
+    let f16s: Vec<half::f16> = u.iter().map(|&x| half::f16::from_f64(x / 1000.)).collect();
+    let [mesh, expected] = samples_meshes::<_, f32>(&f16s, n, 3.3);
+    assert!(!mesh.is_empty());
+    assert_eq!(mesh, expected);
+    let [mesh, expected] = samples_meshes::<_, f64>(&f16s, n, 3.3);
+    assert!(!mesh.is_empty());
+    assert_eq!(mesh, expected);
+}
+
+#[test]
+fn iter_matches_emit() {
+    let n = 8;
+    let u = schwarz_p(n);
+    // the first index of every node as data
+    let data: Vec<f64> = (0..u.len()).map(|i| (i / (n * n)) as f64).collect();
+    let (u, data) = (
+        StridedView::row_major(&u, [n, n, n]),
+        StridedView::row_major(&data, [n, n, n]),
+    );
+    let mask: Vec<bool> = (0..n * n * n).map(|i| i % 7 != 3).collect();
+
+    let mut cases = Vec::new();
+    for decomposition in DECOMPOSITIONS {
+        for (periodic, unwrap) in [
+            ([false; 3], false),
+            ([true, false, true], false),
+            ([true; 3], true),
+        ] {
+            cases.push(Options {
+                decomposition,
+                periodic,
+                unwrap,
+                ..Options::default()
+            });
+        }
+        cases.push(Options {
+            decomposition,
+            mask: Some(&mask),
+            ..Options::default()
+        });
+        cases.push(Options {
+            decomposition,
+            periodic: [true; 3],
+            mask: Some(&mask),
+            ..Options::default()
+        });
+    }
+
+    for options in &cases {
+        let mut expected = Vec::new();
+        marching_tetrahedra_strided_emit(u, data, 0., options, |t, d| expected.push((t, d)));
+        let triangles: Vec<_> = marching_tetrahedra_iter(u, 0., data, options).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(triangles.len(), expected.len(), "{:?}", options);
+        for (t, e) in triangles.iter().zip(&expected) {
+            assert_eq!(t, e, "{:?}", options);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+extern crate isosurface;
+
+use isosurface::{marching_tetrahedra_welded, validate_mesh, Mesh, Options};
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// A sphere of radius 3.3 around the center of an `n`³ grid.
+fn sphere(n: usize) -> Mesh<f64> {
+    let c = (n as f64 - 1.) / 2.;
+    let mut u = Vec::with_capacity(n * n * n);
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let p = [i as f64 - c, j as f64 - c, k as f64 - c];
+                u.push(dot(p, p).sqrt());
+            }
+        }
+    }
+    let data = vec![(); u.len()];
+    marching_tetrahedra_welded(&u, (n, n, n), 3.3, &data, &Options::default())
+}
+
+/// Checks that the faces and the normals of a closed mesh around `center` point outwards.
+fn assert_outward(mesh: &Mesh<f64>, center: [f64; 3]) {
+    let report = validate_mesh(&mesh.verts, &mesh.faces, 0.);
+    assert!(
+        report.is_watertight() && report.is_oriented(),
+        "{:?}",
+        report
+    );
+    for f in &mesh.faces {
+        let [a, b, c] = f.map(|v| mesh.verts[v as usize]);
+        let n = cross(sub(b, a), sub(c, a));
+        assert!(dot(n, sub(a, center)) > 0.);
+    }
+    for (v, n) in mesh.verts.iter().zip(&mesh.normals) {
+        assert!(dot(*n, sub(*v, center)) > 0.);
+    }
+}
+
+#[test]
+fn transform() {
+    let n = 10;
+    let c = (n as f64 - 1.) / 2.;
+    let mut mesh = sphere(n);
+    assert_outward(&mesh, [c; 3]);
+
+    // a rotation with a scaling, and a mirror image of it
+    let rotation = [[0., -2., 0.], [1., 0., 0.], [0., 0., 0.5]];
+    let mirror = [[0., -2., 0.], [-1., 0., 0.], [0., 0., 0.5]];
+    let translation = [10., 20., 30.];
+    let map = |m: [[f64; 3]; 3], v: [f64; 3]| [0, 1, 2].map(|i| dot(m[i], v) + translation[i]);
+    for matrix in [rotation, mirror] {
+        let mut transformed = mesh.clone();
+        transformed.transform(matrix, translation);
+        for (v, w) in mesh.verts.iter().zip(&transformed.verts) {
+            assert_eq!(*w, map(matrix, *v));
+        }
+        assert_outward(&transformed, map(matrix, [c; 3]));
+    }
+
+    mesh.transform([[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]], [0.; 3]);
+    assert_outward(&mesh, [c; 3]);
+}
+
+#[test]
+fn append() {
+    let mut a = sphere(8);
+    let mut b = sphere(10);
+    let (a_verts, a_faces) = (a.verts.len(), a.faces.len());
+    let expected = b.clone();
+
+    a.append(&mut b);
+    assert!(b.is_empty());
+    assert_eq!(a.verts.len(), a_verts + expected.verts.len());
+    assert_eq!(a.normals.len(), a.verts.len());
+    assert_eq!(a.data.len(), a.verts.len());
+    for (f, g) in a.faces[a_faces..].iter().zip(&expected.faces) {
+        assert_eq!(*f, g.map(|v| v + a_verts as u32));
+    }
+    assert_eq!(a.faces.len(), a_faces + expected.faces.len());
+}
+
+#[test]
+fn bounding_box() {
+    assert_eq!(Mesh::<f64>::new().bounding_box(), None);
+
+    let mesh = Mesh {
+        verts: vec![[1., -2., 3.], [0.5, 4., 3.], [2., 0., -1.]],
+        faces: vec![[0, 1, 2]],
+        normals: vec![[0.; 3]; 3],
+        data: vec![(); 3],
+    };
+    assert_eq!(mesh.bounding_box(), Some([[0.5, -2., -1.], [2., 4., 3.]]));
+}
@@ -0,0 +1,88 @@
+extern crate isosurface;
+
+use isosurface::{
+    marching_tetrahedra, marching_tetrahedra_welded, write_msh, Mesh, MshFormat, MshOptions,
+    MshPhysicalGroups, Options,
+};
+
+/// Two separate spheres, as a mesh with separate vertices for every tetrahedron and as a mesh
+/// with shared vertices.
+fn spheres() -> [Mesh<f64>; 2] {
+    let dim = (16, 8, 8);
+    let mut u = Vec::new();
+    for i in 0..dim.0 {
+        for j in 0..dim.1 {
+            for k in 0..dim.2 {
+                let x = [(i % 8) as f64 - 3.5, j as f64 - 3.5, k as f64 - 3.5];
+                u.push((x[0] * x[0] + x[1] * x[1] + x[2] * x[2]).sqrt());
+            }
+        }
+    }
+    let data = vec![(); u.len()];
+    [
+        marching_tetrahedra(&u, dim, 2.5),
+        marching_tetrahedra_welded(&u, dim, 2.5, &data, &Options::default()),
+    ]
+}
+
+/// The lines of the section `name` of an ASCII file.
+fn section<'a>(file: &'a str, name: &str) -> Vec<&'a str> {
+    file.lines()
+        .skip_while(|&l| l != format!("${name}"))
+        .skip(1)
+        .take_while(|&l| l != format!("$End{name}"))
+        .collect()
+}
+
+#[test]
+fn one_entity_per_component() {
+    let options = MshOptions {
+        format: MshFormat::Ascii,
+        physical_groups: MshPhysicalGroups::Components,
+    };
+
+    let mut nodes = Vec::new();
+    for mesh in spheres() {
+        let mut out = Vec::new();
+        write_msh(&mut out, &[("sphere", &mesh)], &options).unwrap();
+        let file = String::from_utf8(out).unwrap();
+
+        let names = section(&file, "PhysicalNames");
+        assert_eq!(names, ["2", "2 1 \"sphere_0\"", "2 2 \"sphere_1\""]);
+
+        // no points or curves, two surfaces and no volumes
+        let entities = section(&file, "Entities");
+        assert_eq!(entities[0], "0 0 2 0");
+        assert_eq!(entities.len(), 3);
+
+        let header = section(&file, "Nodes")[0].to_string();
+        nodes.push(header);
+    }
+
+    // the vertices with the same position are a single node
+    assert_eq!(nodes[0], nodes[1]);
+}
+
+#[test]
+fn one_group_per_level() {
+    let [soup, welded] = spheres();
+    let options = MshOptions {
+        format: MshFormat::Ascii,
+        ..MshOptions::default()
+    };
+    let mut out = Vec::new();
+    write_msh(&mut out, &[("a", &soup), ("b", &welded)], &options).unwrap();
+    let file = String::from_utf8(out).unwrap();
+
+    let names = section(&file, "PhysicalNames");
+    assert_eq!(names, ["2", "2 1 \"a\"", "2 2 \"b\""]);
+
+    // the entities of both spheres of every level have its physical group
+    let entities = section(&file, "Entities");
+    assert_eq!(entities[0], "0 0 4 0");
+    let groups: Vec<&str> = entities[1..]
+        .iter()
+        .map(|e| e.split(' ').nth(8).unwrap())
+        .collect();
+    assert_eq!(groups, ["1", "1", "2", "2"]);
+}
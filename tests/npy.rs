@@ -0,0 +1,284 @@
+extern crate isosurface;
+
+use isosurface::{read_npy, read_npz, write_mesh_npz, write_npy, Mesh, NpyArray, NpyElement};
+use std::io::{Cursor, ErrorKind};
+
+/// Writes and reads the values as a `(2, 3)` array.
+fn round_trip<T: NpyElement + PartialEq + std::fmt::Debug>(values: [T; 6]) {
+    let mut bytes = Vec::new();
+    write_npy(&mut bytes, &values, &[2, 3]).unwrap();
+    // the data is aligned to 64 bytes
+    assert_eq!((bytes.len() - 6 * std::mem::size_of::<T>()) % 64, 0);
+
+    let array: NpyArray<T> = read_npy(&bytes[..]).unwrap();
+    assert_eq!(array.values, values);
+    assert_eq!(array.shape, [2, 3]);
+    assert!(!array.fortran_order);
+}
+
+#[test]
+fn dtypes() {
+    round_trip([0i8, 1, -1, i8::MIN, i8::MAX, 5]);
+    round_trip([0u8, 1, 2, 3, u8::MAX, 5]);
+    round_trip([0i16, 1, -1, i16::MIN, i16::MAX, 5]);
+    round_trip([0u16, 1, 2, 3, u16::MAX, 5]);
+    round_trip([0i32, 1, -1, i32::MIN, i32::MAX, 5]);
+    round_trip([0u32, 1, 2, 3, u32::MAX, 5]);
+    round_trip([0i64, 1, -1, i64::MIN, i64::MAX, 5]);
+    round_trip([0u64, 1, 2, 3, u64::MAX, 5]);
+    round_trip([0f32, 1.5, -1e-3, f32::MIN, f32::MAX, f32::INFINITY]);
+    round_trip([0f64, 1.5, -1e-3, f64::MIN, f64::MAX, f64::NEG_INFINITY]);
+
+    // values are converted into the element type
+    let mut bytes = Vec::new();
+    write_npy(&mut bytes, &[-1i16, 300], &[2]).unwrap();
+    let array: NpyArray<f32> = read_npy(&bytes[..]).unwrap();
+    assert_eq!(array.values, [-1., 300.]);
+    assert_eq!(array.shape, [2]);
+}
+
+/// A `.npy` file with the given header and data.
+fn npy(header: &str, data: &[u8]) -> Vec<u8> {
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.as_bytes());
+    bytes.extend(data);
+    bytes
+}
+
+#[test]
+fn fortran_order() {
+    let data: Vec<u8> = (0..6u16).flat_map(|x| x.to_be_bytes()).collect();
+    let file = npy(
+        "{'descr': '>u2', 'fortran_order': True, 'shape': (2, 3), }\n",
+        &data,
+    );
+    let array: NpyArray<u32> = read_npy(&file[..]).unwrap();
+    assert_eq!(array.values, [0, 1, 2, 3, 4, 5]);
+    assert!(array.fortran_order);
+
+    // the first index varies fastest
+    let view = array.view::<2>();
+    assert_eq!(view.shape(), [2, 3]);
+    assert_eq!(*view.get([1, 0]), 1);
+    assert_eq!(*view.get([0, 2]), 4);
+}
+
+#[test]
+fn half_and_bool() {
+    let data: Vec<u8> = [0x3c00u16, 0xc100, 0x7c00, 0x0001]
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect();
+    let file = npy(
+        "{'descr': '<f2', 'fortran_order': False, 'shape': (4,), }\n",
+        &data,
+    );
+    let array: NpyArray<f64> = read_npy(&file[..]).unwrap();
+    assert_eq!(array.values, [1., -2.5, f64::INFINITY, 2f64.powi(-24)]);
+
+    let file = npy(
+        "{'descr': '|b1', 'fortran_order': False, 'shape': (2,), }\n",
+        &[1, 0],
+    );
+    let array: NpyArray<u8> = read_npy(&file[..]).unwrap();
+    assert_eq!(array.values, [1, 0]);
+}
+
+#[test]
+fn bad_npy() {
+    let header = |descr: &str, shape: &str| {
+        format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}\n")
+    };
+    let cases = [
+        (
+            b"\x93NUMPX\x01\x00\x00\x00".to_vec(),
+            ErrorKind::InvalidData,
+        ),
+        (npy(&header("<c8", "(1,)"), &[0; 8]), ErrorKind::InvalidData),
+        (npy(&header("<f8", "1"), &[0; 8]), ErrorKind::InvalidData),
+        (
+            npy("{'descr': '<f8', 'shape': (1,), }", &[0; 8]),
+            ErrorKind::InvalidData,
+        ),
+        // the number of bytes overflows
+        (
+            npy(&header("<f8", "(4294967296, 4294967296)"), &[]),
+            ErrorKind::InvalidData,
+        ),
+        (
+            npy(&header("<f8", "(2305843009213693952,)"), &[]),
+            ErrorKind::InvalidData,
+        ),
+        // nothing is allocated for the size of the array before the data is read
+        (
+            npy(&header("<f8", "(1000000000000,)"), &[0; 8]),
+            ErrorKind::UnexpectedEof,
+        ),
+        (
+            npy(&header("<f8", "(2,)"), &[0; 15]),
+            ErrorKind::UnexpectedEof,
+        ),
+        (
+            b"\x93NUMPY\x02\x00\xff\xff\xff\xff{}".to_vec(),
+            ErrorKind::UnexpectedEof,
+        ),
+    ];
+    for (file, kind) in cases {
+        let err = read_npy::<_, f64>(&file[..]).unwrap_err();
+        assert_eq!(err.kind(), kind, "{}", String::from_utf8_lossy(&file));
+    }
+}
+
+fn mesh() -> Mesh<f32, f32> {
+    Mesh {
+        verts: vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+        faces: vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]],
+        normals: vec![[-1., -1., -1.], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+        data: vec![0.5, 1.5, 2.5, 3.5],
+    }
+}
+
+fn check_mesh_arrays(arrays: &[(String, NpyArray<f64>)]) {
+    let mesh = mesh();
+    let names: Vec<&str> = arrays.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["verts", "faces", "normals", "data"]);
+
+    let verts: Vec<f64> = mesh
+        .verts
+        .as_flattened()
+        .iter()
+        .map(|&x| x.into())
+        .collect();
+    assert_eq!(arrays[0].1.values, verts);
+    assert_eq!(arrays[0].1.shape, [4, 3]);
+    let faces: Vec<f64> = mesh
+        .faces
+        .as_flattened()
+        .iter()
+        .map(|&x| x.into())
+        .collect();
+    assert_eq!(arrays[1].1.values, faces);
+    let normals: Vec<f64> = mesh
+        .normals
+        .as_flattened()
+        .iter()
+        .map(|&x| x.into())
+        .collect();
+    assert_eq!(arrays[2].1.values, normals);
+    assert_eq!(arrays[3].1.values, [0.5, 1.5, 2.5, 3.5]);
+    assert_eq!(arrays[3].1.shape, [4]);
+}
+
+#[test]
+fn stored_npz() {
+    let mut bytes = Vec::new();
+    write_mesh_npz(&mut bytes, &mesh()).unwrap();
+    check_mesh_arrays(&read_npz(Cursor::new(&bytes)).unwrap());
+
+    // no data array without data
+    let mesh = mesh();
+    let mesh = Mesh {
+        verts: mesh.verts,
+        faces: mesh.faces,
+        normals: mesh.normals,
+        data: vec![(); 4],
+    };
+    let mut bytes = Vec::new();
+    write_mesh_npz(&mut bytes, &mesh).unwrap();
+    let arrays: Vec<(String, NpyArray<f32>)> = read_npz(Cursor::new(&bytes)).unwrap();
+    assert_eq!(arrays.len(), 3);
+}
+
+/// Rewrites a stored ZIP archive with every file compressed by deflate.
+#[cfg(feature = "flate2")]
+fn deflate_zip(stored: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let u16_at = |b: &[u8], p: usize| u16::from_le_bytes([b[p], b[p + 1]]) as usize;
+    let u32_at = |b: &[u8], p: usize| u32::from_le_bytes(b[p..p + 4].try_into().unwrap());
+    let eocd = stored.len() - 22;
+    let n = u16_at(stored, eocd + 10);
+    let mut p = u32_at(stored, eocd + 16) as usize;
+
+    let (mut zip, mut central) = (Vec::new(), Vec::new());
+    for _ in 0..n {
+        let mut entry = stored[p..p + 46].to_vec();
+        let name_len = u16_at(&entry, 28);
+        let name = &stored[p + 46..p + 46 + name_len];
+        let offset = u32_at(&entry, 42) as usize;
+        let size = u32_at(&entry, 20) as usize;
+        let data = &stored[offset + 30 + name_len..offset + 30 + name_len + size];
+
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        // the method and the compressed size in both headers
+        entry[10..12].copy_from_slice(&8u16.to_le_bytes());
+        entry[20..24].copy_from_slice(&(compressed.len() as u32).to_le_bytes());
+        entry[42..46].copy_from_slice(&(zip.len() as u32).to_le_bytes());
+        let mut local = stored[offset..offset + 30].to_vec();
+        local[8..10].copy_from_slice(&8u16.to_le_bytes());
+        local[18..22].copy_from_slice(&(compressed.len() as u32).to_le_bytes());
+
+        zip.extend(local);
+        zip.extend(name);
+        zip.extend(compressed);
+        central.extend(entry);
+        central.extend(name);
+        p += 46 + name_len;
+    }
+
+    let mut end = stored[eocd..].to_vec();
+    end[12..16].copy_from_slice(&(central.len() as u32).to_le_bytes());
+    end[16..20].copy_from_slice(&(zip.len() as u32).to_le_bytes());
+    zip.extend(central);
+    zip.extend(end);
+    zip
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn deflated_npz() {
+    let mut stored = Vec::new();
+    write_mesh_npz(&mut stored, &mesh()).unwrap();
+    let deflated = deflate_zip(&stored);
+    assert!(deflated.len() < stored.len());
+    check_mesh_arrays(&read_npz(Cursor::new(&deflated)).unwrap());
+}
+
+#[test]
+fn bad_npz() {
+    let mut bytes = Vec::new();
+    write_mesh_npz(&mut bytes, &mesh()).unwrap();
+    let eocd = bytes.len() - 22;
+    let cd = u32::from_le_bytes(bytes[eocd + 16..eocd + 20].try_into().unwrap()) as usize;
+    let read = |bytes: &[u8]| read_npz::<_, f64>(Cursor::new(bytes)).unwrap_err();
+
+    // a changed byte in the header of the first array
+    let mut corrupt = bytes.clone();
+    corrupt[30 + "verts.npy".len() + 20] ^= 1;
+    let err = read(&corrupt);
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "CRC mismatch in `verts.npy`");
+
+    // a compressed size beyond the end of the archive
+    let mut corrupt = bytes.clone();
+    corrupt[cd + 20..cd + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = read(&corrupt);
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "`verts.npy` exceeds the ZIP archive");
+
+    // a central directory beyond the end of the archive
+    let mut corrupt = bytes.clone();
+    corrupt[eocd + 12..eocd + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(read(&corrupt).kind(), ErrorKind::InvalidData);
+
+    let mut corrupt = bytes.clone();
+    corrupt[cd + 10..cd + 12].copy_from_slice(&12u16.to_le_bytes());
+    assert_eq!(read(&corrupt).kind(), ErrorKind::InvalidData);
+
+    assert_eq!(read(&bytes[..eocd]).kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,138 @@
+extern crate isosurface;
+
+use isosurface::{
+    marching_tetrahedra, marching_tetrahedra_welded, read_obj, read_obj_with_texture, write_obj,
+    write_obj_with_texture, Mesh, ObjGrouping, ObjOptions, Options,
+};
+use std::io::ErrorKind;
+
+/// Two separate spheres, with the distance from the center of the grid as data.
+fn spheres(welded: bool) -> Mesh<f64, f64> {
+    let dim = (16, 8, 8);
+    let mut u = Vec::new();
+    let mut data = Vec::new();
+    for i in 0..dim.0 {
+        for j in 0..dim.1 {
+            for k in 0..dim.2 {
+                let x = [(i % 8) as f64 - 3.5, j as f64 - 3.5, k as f64 - 3.5];
+                u.push((x[0] * x[0] + x[1] * x[1] + x[2] * x[2]).sqrt());
+                data.push(i as f64 * 0.25);
+            }
+        }
+    }
+    if welded {
+        marching_tetrahedra_welded(&u, dim, 2.5, &data, &Options::default())
+    } else {
+        let mesh = marching_tetrahedra(&u, dim, 2.5);
+        Mesh {
+            data: vec![0.; mesh.verts.len()],
+            verts: mesh.verts,
+            faces: mesh.faces,
+            normals: mesh.normals,
+        }
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mesh = spheres(true);
+    let mut out = Vec::new();
+    write_obj(&mut out, &mesh, &ObjOptions::default()).unwrap();
+
+    let read: Mesh<f64> = read_obj(&out[..]).unwrap();
+    assert_eq!(read.verts, mesh.verts);
+    assert_eq!(read.faces, mesh.faces);
+    assert_eq!(read.normals, mesh.normals);
+    assert_eq!(read.data.len(), mesh.verts.len());
+}
+
+#[test]
+fn round_trip_with_texture() {
+    let mesh = spheres(true);
+    for normals in [false, true] {
+        let options = ObjOptions {
+            normals,
+            ..ObjOptions::default()
+        };
+        let mut out = Vec::new();
+        write_obj_with_texture(&mut out, &mesh, &options, |&d| d).unwrap();
+
+        let read: Mesh<f64, f64> = read_obj_with_texture(&out[..]).unwrap();
+        assert_eq!(read.verts, mesh.verts);
+        assert_eq!(read.faces, mesh.faces);
+        assert_eq!(read.data, mesh.data);
+        if normals {
+            assert_eq!(read.normals, mesh.normals);
+        } else {
+            assert!(read.normals.iter().all(|&n| n == [0.; 3]));
+        }
+    }
+}
+
+#[test]
+fn components() {
+    for welded in [false, true] {
+        let mesh = spheres(welded);
+        for (grouping, keyword) in [(ObjGrouping::Objects, "o "), (ObjGrouping::Groups, "g ")] {
+            let options = ObjOptions {
+                components: Some(grouping),
+                ..ObjOptions::default()
+            };
+            let mut out = Vec::new();
+            write_obj(&mut out, &mesh, &options).unwrap();
+            let file = String::from_utf8(out).unwrap();
+
+            let groups: Vec<&str> = file.lines().filter(|l| l.starts_with(keyword)).collect();
+            assert_eq!(groups.len(), 2, "welded: {}", welded);
+            assert_eq!(groups[1][2..], *"component_1");
+
+            // the faces are only reordered
+            let read: Mesh<f64> = read_obj(file.as_bytes()).unwrap();
+            assert_eq!(read.faces.len(), mesh.faces.len());
+        }
+    }
+}
+
+#[test]
+fn polygons_and_relative_indices() {
+    let file = "\
+# a square and a triangle
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vn 0 0 1
+f 1//1 2//1 3//1 4//1
+v 2 0 0
+f -1 -4 -5
+";
+    let mesh: Mesh<f32> = read_obj(file.as_bytes()).unwrap();
+    assert_eq!(mesh.verts.len(), 5);
+    assert_eq!(mesh.faces, [[0, 1, 2], [0, 2, 3], [4, 1, 0]]);
+    assert_eq!(mesh.normals[3], [0., 0., 1.]);
+    assert_eq!(mesh.normals[4], [0., 0., 0.]);
+}
+
+#[test]
+fn malformed() {
+    let cases = [
+        ("v 0 0\n", "line 1: invalid coordinate"),
+        ("v 0 0 x\n", "line 1: invalid coordinate"),
+        (
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n",
+            "line 4: index out of range",
+        ),
+        (
+            "v 0 0 0\nv 1 0 0\nf 1 2\n",
+            "line 3: face with less than 3 vertices",
+        ),
+        ("v 0 0 0\n\nf 1 1 a\n", "line 3: invalid index"),
+        ("v 0 0 0\nf 1//1 1 1\n", "line 2: index out of range"),
+        ("v 0 0 0\nf /1 1 1\n", "line 2: missing vertex index"),
+    ];
+    for (file, msg) in cases {
+        let err = read_obj::<_, f64>(file.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{:?}", file);
+        assert_eq!(err.to_string(), msg, "{:?}", file);
+    }
+}
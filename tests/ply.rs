@@ -0,0 +1,104 @@
+extern crate isosurface;
+
+use isosurface::{
+    marching_tetrahedra_sink, write_ply, Mesh, Options, PlyFormat, PlyOptions, PlyWriter,
+    StridedView,
+};
+
+/// A single triangle in the plane `z = 0`.
+fn triangle() -> Mesh<f32> {
+    let mut mesh = Mesh::new();
+    mesh.verts = vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]];
+    mesh.normals = vec![[0., 0., 1.]; 3];
+    mesh.data = vec![(); 3];
+    mesh.faces = vec![[0, 1, 2]];
+    mesh
+}
+
+/// Splits a PLY file into its header and body.
+fn split(bytes: &[u8]) -> (String, &[u8]) {
+    let end = b"end_header\n";
+    let i = bytes.windows(end.len()).position(|w| w == end).unwrap() + end.len();
+    (String::from_utf8(bytes[..i].to_vec()).unwrap(), &bytes[i..])
+}
+
+#[test]
+fn faces_are_unsigned() {
+    let options = PlyOptions {
+        normals: false,
+        ..PlyOptions::default()
+    };
+    let mut out = Vec::new();
+    write_ply(&mut out, &triangle(), &options).unwrap();
+
+    let (header, body) = split(&out);
+    assert!(header.contains("property list uchar uint vertex_indices\n"));
+
+    // three vertices of three floats, then the face
+    let face = &body[3 * 12..];
+    assert_eq!(face.len(), 1 + 3 * 4);
+    assert_eq!(face[0], 3);
+    let indices: Vec<u32> = face[1..]
+        .chunks(4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    assert_eq!(indices, vec![0, 1, 2]);
+}
+
+#[test]
+fn ascii() {
+    let options = PlyOptions {
+        format: PlyFormat::Ascii,
+        ..PlyOptions::default()
+    };
+    let mut out = Vec::new();
+    write_ply(&mut out, &triangle(), &options).unwrap();
+
+    let (header, body) = split(&out);
+    assert!(header.starts_with("ply\nformat ascii 1.0\n"));
+    assert!(header.contains("element vertex 3\n"));
+    assert!(header.contains("element face 1\n"));
+    let lines: Vec<&str> = std::str::from_utf8(body).unwrap().lines().collect();
+    assert_eq!(
+        lines,
+        ["0 0 0 0 0 1", "1 0 0 0 0 1", "0 1 0 0 0 1", "3 0 1 2"]
+    );
+}
+
+#[test]
+fn streamed_file_matches_write_ply() {
+    let n = 8;
+    let c = (n as f64 - 1.) / 2.;
+    let mut u = Vec::new();
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let x = [i, j, k].map(|i| i as f64 - c);
+                u.push((x[0] * x[0] + x[1] * x[1] + x[2] * x[2]).sqrt());
+            }
+        }
+    }
+    // the values as data
+    let data = StridedView::row_major(&u, [n; 3]);
+    let u = StridedView::row_major(&u, [n; 3]);
+
+    for format in [PlyFormat::Ascii, PlyFormat::BinaryBigEndian] {
+        let options = PlyOptions {
+            format,
+            ..PlyOptions::default()
+        };
+
+        let mut mesh = Mesh::new();
+        marching_tetrahedra_sink(u, 2.5, data, &Options::default(), &mut mesh);
+        let mut expected = Vec::new();
+        write_ply(&mut expected, &mesh, &options).unwrap();
+
+        // the output does not need to be seekable
+        let mut writer = PlyWriter::new(Vec::new(), &options).unwrap();
+        marching_tetrahedra_sink(u, 2.5, data, &Options::default(), &mut writer);
+        assert_eq!(writer.len(), mesh.faces.len());
+        let out = writer.finish().unwrap();
+
+        assert_eq!(out, expected);
+    }
+}
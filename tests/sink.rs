@@ -0,0 +1,72 @@
+extern crate isosurface;
+
+use isosurface::{
+    marching_tetrahedra_sink, marching_tetrahedra_welded_sink, CountingSink, Mesh, Options,
+    StridedView, TriangleSoup,
+};
+
+/// A sphere on an `n`³ grid, with the first coordinate of the nodes as data.
+fn sphere(n: usize) -> (Vec<f64>, Vec<f64>) {
+    let c = (n as f64 - 1.) / 2.;
+    let mut u = Vec::with_capacity(n * n * n);
+    let mut data = Vec::with_capacity(n * n * n);
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let (x, y, z) = (i as f64 - c, j as f64 - c, k as f64 - c);
+                u.push((x * x + y * y + z * z).sqrt());
+                data.push(i as f64);
+            }
+        }
+    }
+    (u, data)
+}
+
+#[test]
+fn counting_sink_matches_mesh() {
+    let n = 10;
+    let (u, data) = sphere(n);
+    let u = StridedView::row_major(&u, [n, n, n]);
+    let data = StridedView::row_major(&data, [n, n, n]);
+    let options = Options::default();
+
+    let (mut mesh, mut counts) = (Mesh::new(), CountingSink::default());
+    marching_tetrahedra_sink(u, 3.3, data, &options, &mut mesh);
+    marching_tetrahedra_sink(u, 3.3, data, &options, &mut counts);
+    assert!(!mesh.is_empty());
+    assert_eq!(counts.vertices, mesh.verts.len());
+    assert_eq!(counts.faces, mesh.faces.len());
+
+    let (mut mesh, mut counts) = (Mesh::new(), CountingSink::default());
+    marching_tetrahedra_welded_sink(u, 3.3, data, &options, &mut mesh);
+    marching_tetrahedra_welded_sink(u, 3.3, data, &options, &mut counts);
+    assert!(!mesh.is_empty());
+    assert_eq!(counts.vertices, mesh.verts.len());
+    assert_eq!(counts.faces, mesh.faces.len());
+}
+
+#[test]
+fn triangle_soup_has_unshared_vertices() {
+    let n = 10;
+    let (u, data) = sphere(n);
+    let u = StridedView::row_major(&u, [n, n, n]);
+    let data = StridedView::row_major(&data, [n, n, n]);
+    let options = Options::default();
+
+    let (mut mesh, mut soup) = (Mesh::new(), TriangleSoup::new());
+    marching_tetrahedra_welded_sink(u, 3.3, data, &options, &mut mesh);
+    marching_tetrahedra_welded_sink(u, 3.3, data, &options, &mut soup);
+
+    // every face has its own three vertices, even though the mesh shares them
+    assert!(mesh.verts.len() < 3 * mesh.faces.len());
+    assert_eq!(soup.triangles.len(), mesh.faces.len());
+    assert_eq!(soup.data.len(), mesh.faces.len());
+    assert_eq!(
+        soup.triangles.iter().flatten().count(),
+        3 * mesh.faces.len()
+    );
+    for ((t, d), f) in soup.triangles.iter().zip(&soup.data).zip(&mesh.faces) {
+        assert_eq!(*t, f.map(|v| mesh.verts[v as usize]));
+        assert_eq!(*d, f.map(|v| mesh.data[v as usize]));
+    }
+}
@@ -0,0 +1,156 @@
+extern crate isosurface;
+
+use isosurface::{marching_tetrahedra, write_stl, Mesh, StlFormat, StlOptions, StlWriter};
+use std::io::Cursor;
+
+/// A sphere of radius 2.5 around the center of an 8³ grid.
+fn sphere() -> Mesh<f64> {
+    let n = 8;
+    let mut u = Vec::new();
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let x = [i, j, k].map(|i| i as f64 - 3.5);
+                u.push((x[0] * x[0] + x[1] * x[1] + x[2] * x[2]).sqrt());
+            }
+        }
+    }
+    marching_tetrahedra(&u, (n, n, n), 2.5)
+}
+
+/// A single triangle in the _xy_ plane, counterclockwise when seen from +z.
+fn triangle() -> Mesh<f64> {
+    let mut mesh = Mesh::new();
+    mesh.verts = vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]];
+    mesh.faces = vec![[0, 1, 2]];
+    mesh.normals = vec![[0., 0., 1.]; 3];
+    mesh.data = vec![(); 3];
+    mesh
+}
+
+fn floats(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect()
+}
+
+#[test]
+fn binary_layout() {
+    let mesh = sphere();
+    assert!(!mesh.faces.is_empty());
+    let mut out = Vec::new();
+    write_stl(&mut out, &mesh, &StlOptions::default()).unwrap();
+
+    assert_eq!(out.len(), 80 + 4 + 50 * mesh.faces.len());
+    assert!(!out.starts_with(b"solid"));
+    let count = u32::from_le_bytes(out[80..84].try_into().unwrap());
+    assert_eq!(count as usize, mesh.faces.len());
+
+    for (facet, f) in out[84..].chunks(50).zip(&mesh.faces) {
+        let values = floats(&facet[..48]);
+        let n = &values[..3];
+        assert!((n.iter().map(|x| x * x).sum::<f32>() - 1.).abs() < 1e-5);
+        for (v, &vi) in values[3..].chunks(3).zip(f) {
+            let expected = mesh.verts[vi as usize].map(|x| x as f32);
+            assert_eq!(v, expected);
+        }
+        assert_eq!(facet[48..], [0, 0]);
+    }
+}
+
+#[test]
+fn ascii_layout() {
+    let mesh = sphere();
+    let options = StlOptions {
+        format: StlFormat::Ascii,
+        ..StlOptions::default()
+    };
+    let mut out = Vec::new();
+    write_stl(&mut out, &mesh, &options).unwrap();
+    let text = String::from_utf8(out).unwrap();
+
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "solid isosurface");
+    assert_eq!(*lines.last().unwrap(), "endsolid isosurface");
+    assert_eq!(lines.len(), 2 + 7 * mesh.faces.len());
+
+    let vertices: Vec<[f64; 3]> = lines
+        .iter()
+        .filter_map(|l| l.trim().strip_prefix("vertex "))
+        .map(|l| {
+            let x: Vec<f64> = l.split(' ').map(|x| x.parse().unwrap()).collect();
+            [x[0], x[1], x[2]]
+        })
+        .collect();
+    let expected: Vec<[f64; 3]> = mesh
+        .faces
+        .iter()
+        .flat_map(|f| f.map(|v| mesh.verts[v as usize]))
+        .collect();
+    assert_eq!(vertices, expected);
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|l| l.starts_with("facet normal "))
+            .count(),
+        mesh.faces.len()
+    );
+}
+
+#[test]
+fn spacing_and_origin() {
+    let options = StlOptions {
+        format: StlFormat::Ascii,
+        spacing: [2., 3., 4.],
+        origin: [1., -1., 0.5],
+    };
+    let mut out = Vec::new();
+    write_stl(&mut out, &triangle(), &options).unwrap();
+    let text = String::from_utf8(out).unwrap();
+
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    assert_eq!(lines[1], "facet normal 0 0 1");
+    assert_eq!(lines[3], "vertex 1 -1 0.5");
+    assert_eq!(lines[4], "vertex 3 -1 0.5");
+    assert_eq!(lines[5], "vertex 1 2 0.5");
+
+    // a mirrored axis flips the normal along with the orientation
+    let options = StlOptions {
+        format: StlFormat::Binary,
+        spacing: [-1., 1., 1.],
+        origin: [0.; 3],
+    };
+    let mut out = Vec::new();
+    write_stl(&mut out, &triangle(), &options).unwrap();
+    let values = floats(&out[84..132]);
+    assert_eq!(values[..3], [0., 0., -1.]);
+    assert_eq!(values[6], -1.);
+}
+
+#[test]
+fn writer_matches_write_stl() {
+    let mesh = sphere();
+    for format in [StlFormat::Ascii, StlFormat::Binary] {
+        let options = StlOptions {
+            format,
+            spacing: [0.5; 3],
+            ..StlOptions::default()
+        };
+        let mut expected = Vec::new();
+        write_stl(&mut expected, &mesh, &options).unwrap();
+
+        // the file starts after some other content
+        let mut cursor = Cursor::new(b"prefix".to_vec());
+        cursor.set_position(6);
+        let mut writer = StlWriter::new(cursor, &options).unwrap();
+        for f in &mesh.faces {
+            writer.triangle(f.map(|v| mesh.verts[v as usize]));
+        }
+        assert_eq!(writer.len(), mesh.faces.len());
+        let out = writer.finish().unwrap().into_inner();
+
+        assert_eq!(&out[..6], b"prefix");
+        assert_eq!(out[6..], expected[..]);
+    }
+}
@@ -0,0 +1,124 @@
+extern crate isosurface;
+
+use isosurface::validate_mesh;
+
+/// A closed tetrahedron with outward facing faces.
+fn tetrahedron() -> (Vec<[f64; 3]>, Vec<[u32; 3]>) {
+    let verts = vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+    let faces = vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]];
+    (verts, faces)
+}
+
+#[test]
+fn closed_tetrahedron_is_valid() {
+    let (verts, faces) = tetrahedron();
+    let report = validate_mesh(&verts, &faces, 0.);
+    assert!(report.is_valid(), "{:?}", report);
+}
+
+#[test]
+fn duplicate_vertices_are_merged() {
+    let (verts, faces) = tetrahedron();
+    // a separate copy of the vertices for every face
+    let soup: Vec<[f64; 3]> = faces
+        .iter()
+        .flat_map(|f| f.map(|v| verts[v as usize]))
+        .collect();
+    let soup_faces: Vec<[u32; 3]> = (0..faces.len() as u32)
+        .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+        .collect();
+
+    let report = validate_mesh(&soup, &soup_faces, 0.);
+    assert_eq!(report.duplicate_vertices, 8);
+    assert!(report.is_watertight());
+    assert!(report.is_oriented());
+    // duplicates are reported only
+    assert!(report.is_valid());
+}
+
+#[test]
+fn boundary_edges() {
+    let verts = [[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]];
+    let report = validate_mesh(&verts, &[[0, 1, 2]], 0.);
+    assert_eq!(report.boundary_edges, vec![[0, 1], [0, 2], [1, 2]]);
+    assert!(report.is_manifold());
+    assert!(!report.is_watertight());
+}
+
+#[test]
+fn non_manifold_edge() {
+    // three triangles around the edge 0-1
+    let verts = [
+        [0., 0., 0.],
+        [1., 0., 0.],
+        [0., 1., 0.],
+        [0., -1., 0.],
+        [0., 0., 1.],
+    ];
+    let faces = [[0, 1, 2], [1, 0, 3], [0, 1, 4]];
+    let report = validate_mesh(&verts, &faces, 0.);
+    assert_eq!(report.non_manifold_edges, vec![[0, 1]]);
+    assert!(!report.is_manifold());
+}
+
+#[test]
+fn non_manifold_vertex() {
+    // two triangles touching at the vertex 0
+    let verts = [
+        [0., 0., 0.],
+        [1., 0., 0.],
+        [1., 1., 0.],
+        [-1., 0., 0.],
+        [-1., -1., 0.],
+    ];
+    let faces = [[0, 1, 2], [0, 3, 4]];
+    let report = validate_mesh(&verts, &faces, 0.);
+    assert_eq!(report.non_manifold_vertices, vec![0]);
+    assert!(report.non_manifold_edges.is_empty());
+    assert!(!report.is_manifold());
+}
+
+#[test]
+fn inconsistent_orientation() {
+    let (verts, mut faces) = tetrahedron();
+    faces[3] = [1, 3, 2];
+    let report = validate_mesh(&verts, &faces, 0.);
+    assert_eq!(report.inconsistent_edges, vec![[1, 2], [1, 3], [2, 3]]);
+    assert!(report.is_watertight());
+    assert!(!report.is_oriented());
+}
+
+#[test]
+fn nan_vertices() {
+    let (mut verts, faces) = tetrahedron();
+    verts[3][1] = f64::NAN;
+    let report = validate_mesh(&verts, &faces, 0.);
+    assert_eq!(report.nan_vertices, vec![3]);
+    // the faces with the NaN vertex have undefined quality
+    assert_eq!(report.degenerate_faces, vec![1, 2, 3]);
+    assert!(!report.is_valid());
+}
+
+#[test]
+fn quality_threshold() {
+    // an equilateral triangle and a thin one
+    let h = 3f64.sqrt() / 2.;
+    let verts = [
+        [0., 0., 0.],
+        [1., 0., 0.],
+        [0.5, h, 0.],
+        [0.5, 0.05, 0.],
+        [0.5, 0., 0.],
+    ];
+    let faces = [[0, 1, 2], [0, 1, 3], [0, 4, 1]];
+
+    let report = validate_mesh(&verts, &faces, 0.);
+    // only the zero-area face
+    assert_eq!(report.degenerate_faces, vec![2]);
+
+    let report = validate_mesh(&verts, &faces, 0.2);
+    assert_eq!(report.degenerate_faces, vec![1, 2]);
+
+    let report = validate_mesh(&verts, &faces, 1.);
+    assert_eq!(report.degenerate_faces, vec![0, 1, 2]);
+}
@@ -0,0 +1,95 @@
+extern crate isosurface;
+
+use isosurface::{marching_tetrahedra_strided, Options, StridedView};
+
+/// Values of a distance function at the nodes of an `n`³ grid, indexed by `[i, j, k]`.
+fn value(n: usize, [i, j, k]: [usize; 3]) -> f64 {
+    let c = (n as f64 - 1.) / 2.;
+    let (x, y, z) = (i as f64 - c, 1.3 * (j as f64 - c), k as f64 - c - 0.4);
+    (x * x + y * y + z * z).sqrt()
+}
+
+/// The values in the order given by `position`, the index of a node in the storage.
+fn store(n: usize, position: impl Fn([usize; 3]) -> usize) -> Vec<f64> {
+    let mut u = vec![0.; n * n * n];
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                u[position([i, j, k])] = value(n, [i, j, k]);
+            }
+        }
+    }
+    u
+}
+
+#[test]
+fn layouts_give_the_same_mesh() {
+    let n = 9;
+    let row_major = store(n, |[i, j, k]| (i * n + j) * n + k);
+    let column_major = store(n, |[i, j, k]| (k * n + j) * n + i);
+    let reversed = store(n, |[i, j, k]| ((n - 1 - i) * n + j) * n + (n - 1 - k));
+    let data = vec![(); n * n * n];
+    let data = StridedView::row_major(&data, [n, n, n]);
+    let options = Options::default();
+
+    let views = [
+        StridedView::row_major(&row_major, [n, n, n]),
+        StridedView::column_major(&column_major, [n, n, n]),
+        // negative strides along the first and the last axis
+        StridedView::new(
+            &reversed,
+            (n - 1) * n * n + n - 1,
+            [n, n, n],
+            [-((n * n) as isize), n as isize, -1],
+        ),
+    ];
+    for view in &views {
+        assert_eq!(*view.get([1, 2, 3]), value(n, [1, 2, 3]));
+    }
+
+    let expected = marching_tetrahedra_strided(views[0], 2.5, data, &options);
+    assert!(!expected.is_empty());
+    for view in &views[1..] {
+        assert_eq!(
+            marching_tetrahedra_strided(*view, 2.5, data, &options),
+            expected
+        );
+    }
+}
+
+#[test]
+fn empty_view() {
+    let view = StridedView::<f64, 3>::new(&[], 5, [2, 0, 3], [isize::MAX, 1, 1]);
+    assert_eq!(view.shape(), [2, 0, 3]);
+}
+
+#[test]
+#[should_panic(expected = "strided view out of bounds")]
+fn view_beyond_the_end() {
+    StridedView::new(&[0.; 6], 1, [2, 3], [3, 1]);
+}
+
+#[test]
+#[should_panic(expected = "strided view out of bounds")]
+fn view_before_the_start() {
+    StridedView::new(&[0.; 6], 1, [2, 3], [3, -1]);
+}
+
+#[test]
+#[should_panic(expected = "strided view out of bounds")]
+fn overflowing_strides() {
+    // the largest offset wraps around to a negative one without checked arithmetic
+    StridedView::new(&[0.; 4], 0, [2, 2], [isize::MAX, 1]);
+}
+
+#[test]
+#[should_panic(expected = "strided view out of bounds")]
+fn overflowing_extent() {
+    StridedView::new(&[0.; 4], 0, [usize::MAX, 1], [2, 1]);
+}
+
+#[test]
+#[should_panic(expected = "strided view out of bounds")]
+fn overflowing_offset() {
+    StridedView::new(&[0.; 4], usize::MAX, [1, 1], [1, 1]);
+}
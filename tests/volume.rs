@@ -0,0 +1,446 @@
+extern crate isosurface;
+
+use isosurface::{
+    read_metaimage, read_metaimage_file, read_nifti, read_nifti_file, read_nrrd, read_nrrd_file,
+    read_raw, RawOptions, SampleType, Samples,
+};
+use std::io::{self, BufRead, ErrorKind, Read};
+use std::path::PathBuf;
+
+/// A path in the temporary directory, unique to the process.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("isosurface-test-{}-{}", std::process::id(), name))
+}
+
+/// A NRRD header for a 2×2×2 volume with the given type and extra fields.
+fn nrrd_header(ty: &str, fields: &str) -> Vec<u8> {
+    format!("NRRD0004\n# a comment\ntype: {ty}\ndimension: 3\nsizes: 2 2 2\n{fields}\n")
+        .into_bytes()
+}
+
+#[test]
+fn nrrd_raw() {
+    let values: Vec<u16> = (0..8).map(|i| 1000 * i + 1).collect();
+    for (endian, bytes) in [
+        (
+            "little",
+            values
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<u8>>(),
+        ),
+        ("big", values.iter().flat_map(|x| x.to_be_bytes()).collect()),
+    ] {
+        let fields = format!(
+            "encoding: raw\nendian: {endian}\nspace directions: (0.5,0,0) (0,1,0) (0,0,2)\n\
+             space origin: (1,2,3)\n"
+        );
+        let mut file = nrrd_header("ushort", &fields);
+        file.extend(&bytes);
+
+        let volume = read_nrrd(&file[..]).unwrap();
+        assert_eq!(volume.samples, Samples::U16(values.clone()));
+        assert_eq!(volume.shape, [2, 2, 2]);
+        assert_eq!(volume.spacing(), [0.5, 1., 2.]);
+        assert_eq!(volume.origin, [1., 2., 3.]);
+    }
+}
+
+#[test]
+fn nrrd_ascii_and_skips() {
+    let file = nrrd_header("float", "encoding: ascii\nspacings: 2 2 nan\n");
+    let mut file = file;
+    file.extend(b"0 1 2 3\n4 5 6 7.5\n");
+    let volume = read_nrrd(&file[..]).unwrap();
+    assert_eq!(
+        volume.samples,
+        Samples::F32(vec![0., 1., 2., 3., 4., 5., 6., 7.5])
+    );
+    assert_eq!(volume.spacing(), [2., 2., 1.]);
+
+    // the data at the end of the file, after lines to skip
+    let mut file = nrrd_header("uchar", "encoding: raw\nline skip: 1\nbyte skip: -1\n");
+    file.extend(b"a line\n");
+    file.extend(b"xx");
+    file.extend(0..8);
+    let volume = read_nrrd(&file[..]).unwrap();
+    assert_eq!(volume.samples, Samples::U8((0..8).collect()));
+}
+
+/// A reader that fails, to check that nothing is read after the data.
+struct Failing;
+
+impl Read for Failing {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("read past the data"))
+    }
+}
+
+impl BufRead for Failing {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Err(io::Error::other("read past the data"))
+    }
+
+    fn consume(&mut self, _amt: usize) {}
+}
+
+#[test]
+fn nrrd_raw_reads_only_the_data() {
+    let mut file = nrrd_header("uchar", "encoding: raw\nline skip: 1\nbyte skip: 2\n");
+    file.extend(b"a line\n");
+    file.extend(b"xx");
+    file.extend(0..8);
+    let volume = read_nrrd((&file[..]).chain(Failing)).unwrap();
+    assert_eq!(volume.samples, Samples::U8((0..8).collect()));
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn nrrd_gzip() {
+    use std::io::Write;
+
+    let values: Vec<f64> = (0..8).map(|i| i as f64 / 4.).collect();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    for x in &values {
+        encoder.write_all(&x.to_be_bytes()).unwrap();
+    }
+    let mut file = nrrd_header("double", "encoding: gzip\nendian: big\n");
+    file.extend(encoder.finish().unwrap());
+
+    let volume = read_nrrd(&file[..]).unwrap();
+    assert_eq!(volume.samples, Samples::F64(values));
+}
+
+#[test]
+fn nrrd_detached() {
+    let header = temp_path("detached.nhdr");
+    let data = temp_path("detached.raw");
+    let fields = format!(
+        "encoding: raw\nendian: little\ndata file: {}\n",
+        data.file_name().unwrap().to_str().unwrap()
+    );
+    std::fs::write(&header, nrrd_header("short", &fields)).unwrap();
+    let values: Vec<i16> = (0..8).map(|i| -i).collect();
+    let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+    std::fs::write(&data, bytes).unwrap();
+
+    let volume = read_nrrd_file(&header);
+    std::fs::remove_file(&header).unwrap();
+    std::fs::remove_file(&data).unwrap();
+    assert_eq!(volume.unwrap().samples, Samples::I16(values));
+
+    // the data file is required
+    let err = read_nrrd(&nrrd_header("short", &fields)[..]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn nrrd_bad_headers() {
+    let cases: [&[u8]; 13] = [
+        b"P5\n",
+        b"NRRD0004\ntype: uchar\ndimension: 3\nsizes: 2 2 2\n\n",
+        b"NRRD0004\ntype: int\ndimension: 3\nsizes: 2 2 2\nencoding: raw\n\n",
+        b"NRRD0004\ntype: uchar\ndimension: 2\nsizes: 2 2\nencoding: raw\n\n",
+        b"NRRD0004\ntype: uchar\ndimension: 3\nsizes: 2 2\nencoding: raw\n\n",
+        b"NRRD0004\ntype: uchar\ndimension: 3\nsizes: 2 2 2\nencoding: bzip2\n\n",
+        b"NRRD0004\ntype: uchar\ndimension: 3\nsizes: 2 2 2\nencoding: raw\nbyte skip: -2\n\n",
+        // integer fields that are not integers, with enough data otherwise
+        b"NRRD0004\ntype: uchar\ndimension: 3.5\nsizes: 2 2 2\nencoding: raw\n\n12345678",
+        b"NRRD0004\ntype: uchar\ndimension: 3\nsizes: 2 2 2\nencoding: raw\nline skip: -4\n\n\
+          12345678",
+        b"NRRD0004\ntype: uchar\ndimension: 3\nsizes: 2 2 2\nencoding: raw\nbyte skip: 1.5\n\n\
+          12345678",
+        // too few samples
+        b"NRRD0004\ntype: uchar\ndimension: 3\nsizes: 2 2 2\nencoding: raw\n\n1234567",
+        // multi-byte samples without a byte order
+        b"NRRD0004\ntype: short\ndimension: 3\nsizes: 1 1 1\nencoding: raw\n\n12",
+        // the number of bytes overflows
+        b"NRRD0004\ntype: double\ndimension: 3\nsizes: 4294967296 4294967296 4294967296\n\
+          encoding: raw\n\n",
+    ];
+    for file in cases {
+        let err = read_nrrd(file).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::InvalidData,
+            "{}",
+            String::from_utf8_lossy(file)
+        );
+    }
+
+    // large skips end at the end of the data
+    let file = b"NRRD0004\ntype: uchar\ndimension: 3\nsizes: 1 1 1\nencoding: raw\n\
+                 line skip: 18446744073709551615\nbyte skip: 9223372036854775807\n\n1\n";
+    let err = read_nrrd(&file[..]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn raw() {
+    let mut bytes = vec![0xff; 3];
+    bytes.extend((0..8).flat_map(|i| (i as f32).to_be_bytes()));
+    let options = RawOptions {
+        byte_order: isosurface::ByteOrder::BigEndian,
+        skip: 3,
+        spacing: [1., 2., 3.],
+        ..RawOptions::default()
+    };
+    let volume = read_raw(&bytes[..], [2, 2, 2], SampleType::F32, &options).unwrap();
+    assert_eq!(
+        volume.samples.to_f64(),
+        (0..8).map(f64::from).collect::<Vec<_>>()
+    );
+    assert_eq!(volume.spacing(), [1., 2., 3.]);
+
+    let err = read_raw(&bytes[..], [2, 2, 3], SampleType::F32, &options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+    // nothing is allocated for the size of the volume before the data is read
+    let huge = [1 << 20, 1 << 20, 1 << 10];
+    let err = read_raw(&bytes[..], huge, SampleType::U8, &options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    let err = read_raw(&bytes[..], [usize::MAX, 2, 1], SampleType::U8, &options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = read_raw(&bytes[..], [usize::MAX, 1, 1], SampleType::U16, &options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+/// Writes the little- or the big-endian bytes of a field at `offset`.
+fn put<const N: usize>(bytes: &mut [u8], offset: usize, le: [u8; N], be: [u8; N], big: bool) {
+    bytes[offset..offset + N].copy_from_slice(if big { &be } else { &le });
+}
+
+/// A NIfTI-1 file with a 2×2×2 `int16` volume scaled by 2 and offset by 1, positioned by its
+/// sform.
+fn nifti1(big: bool, magic: &[u8; 4], dim: [i16; 4]) -> Vec<u8> {
+    let mut file = vec![0; 352];
+    put(
+        &mut file,
+        0,
+        348i32.to_le_bytes(),
+        348i32.to_be_bytes(),
+        big,
+    );
+    for (i, d) in dim.into_iter().enumerate() {
+        put(&mut file, 40 + 2 * i, d.to_le_bytes(), d.to_be_bytes(), big);
+    }
+    put(&mut file, 70, 4i16.to_le_bytes(), 4i16.to_be_bytes(), big);
+    put(
+        &mut file,
+        108,
+        352f32.to_le_bytes(),
+        352f32.to_be_bytes(),
+        big,
+    );
+    put(&mut file, 112, 2f32.to_le_bytes(), 2f32.to_be_bytes(), big);
+    put(&mut file, 116, 1f32.to_le_bytes(), 1f32.to_be_bytes(), big);
+    put(&mut file, 254, 1i16.to_le_bytes(), 1i16.to_be_bytes(), big);
+    let srow = [[0., -3., 0., 10.], [2., 0., 0., 20.], [0., 0., 4., 30.]];
+    for (r, row) in srow.iter().enumerate() {
+        for (c, &x) in row.iter().enumerate() {
+            let x = x as f32;
+            put(
+                &mut file,
+                280 + 16 * r + 4 * c,
+                x.to_le_bytes(),
+                x.to_be_bytes(),
+                big,
+            );
+        }
+    }
+    file[344..348].copy_from_slice(magic);
+    for i in 0..8i16 {
+        let x = i - 4;
+        file.extend(if big {
+            x.to_be_bytes()
+        } else {
+            x.to_le_bytes()
+        });
+    }
+    file
+}
+
+/// A NIfTI-2 file with a 2×2×2 `float64` volume positioned by its voxel sizes.
+fn nifti2(dim: [i64; 4]) -> Vec<u8> {
+    let mut file = vec![0; 544];
+    file[..4].copy_from_slice(&540i32.to_le_bytes());
+    file[4..12].copy_from_slice(b"n+2\0\r\n\x1a\n");
+    file[12..14].copy_from_slice(&64i16.to_le_bytes());
+    for (i, d) in dim.into_iter().enumerate() {
+        file[16 + 8 * i..24 + 8 * i].copy_from_slice(&d.to_le_bytes());
+    }
+    for (i, s) in [1., 0.5, 0.25, 2.].into_iter().enumerate() {
+        file[104 + 8 * i..112 + 8 * i].copy_from_slice(&f64::to_le_bytes(s));
+    }
+    file[168..176].copy_from_slice(&544i64.to_le_bytes());
+    file.extend((0..8).flat_map(|i| (i as f64).to_le_bytes()));
+    file
+}
+
+#[test]
+fn nifti() {
+    for big in [false, true] {
+        let volume = read_nifti(&nifti1(big, b"n+1\0", [3, 2, 2, 2])[..]).unwrap();
+        let expected = (0..8).map(|i| 2. * (i - 4) as f32 + 1.).collect();
+        assert_eq!(volume.samples, Samples::F32(expected));
+        assert_eq!(volume.shape, [2, 2, 2]);
+        assert_eq!(
+            volume.directions,
+            [[0., 2., 0.], [-3., 0., 0.], [0., 0., 4.]]
+        );
+        assert_eq!(volume.origin, [10., 20., 30.]);
+    }
+
+    let volume = read_nifti(&nifti2([3, 2, 2, 2])[..]).unwrap();
+    assert_eq!(
+        volume.samples,
+        Samples::F64((0..8).map(f64::from).collect())
+    );
+    assert_eq!(volume.spacing(), [0.5, 0.25, 2.]);
+    assert_eq!(volume.origin, [0.; 3]);
+
+    // a 2-dimensional image is a volume with a single slice
+    let mut file = nifti2([2, 4, 2, 7]);
+    file.truncate(544 + 8 * 8);
+    assert_eq!(read_nifti(&file[..]).unwrap().shape, [4, 2, 1]);
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn nifti_gzip() {
+    use std::io::Write;
+
+    let file = nifti1(false, b"n+1\0", [3, 2, 2, 2]);
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&file).unwrap();
+    let volume = read_nifti(&encoder.finish().unwrap()[..]).unwrap();
+    assert_eq!(volume, read_nifti(&file[..]).unwrap());
+}
+
+#[test]
+fn nifti_pair() {
+    let header = temp_path("pair.hdr");
+    let image = temp_path("pair.img");
+    let file = nifti1(true, b"ni1\0", [3, 2, 2, 2]);
+    // the image of a pair starts at the offset in its own file
+    std::fs::write(&header, &file[..352]).unwrap();
+    std::fs::write(&image, &file).unwrap();
+
+    let volume = read_nifti_file(&header);
+    std::fs::remove_file(&header).unwrap();
+    std::fs::remove_file(&image).unwrap();
+    assert_eq!(volume.unwrap().samples.len(), 8);
+
+    let err = read_nifti(&file[..]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn nifti_bad_headers() {
+    let mut files = vec![
+        b"n+1\0".to_vec(),
+        nifti1(false, b"n+1\0", [3, 2, 2, 2])[..300].to_vec(),
+        nifti1(false, b"xyz\0", [3, 2, 2, 2]),
+        nifti1(false, b"n+1\0", [4, 2, 2, 2]),
+        nifti1(false, b"n+1\0", [3, -2, 2, 2]),
+        // too few samples
+        nifti1(false, b"n+1\0", [3, 2, 2, 3]),
+        nifti1(false, b"n+1\0", [3, i16::MAX, i16::MAX, i16::MAX]),
+        // the number of samples overflows
+        nifti2([3, 1 << 32, 1 << 32, 1 << 32]),
+        // the number of bytes overflows
+        nifti2([3, 1 << 61, 1, 1]),
+    ];
+    let mut unsupported = nifti2([3, 2, 2, 2]);
+    unsupported[12..14].copy_from_slice(&8i16.to_le_bytes());
+    files.push(unsupported);
+    for file in files {
+        let err = read_nifti(&file[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn metaimage() {
+    let header = temp_path("image.mhd");
+    let data = temp_path("image.raw");
+    let name = data.file_name().unwrap().to_str().unwrap();
+    std::fs::write(
+        &header,
+        format!(
+            "ObjectType = Image\nNDims = 3\nDimSize = 2 2 2\nElementType = MET_USHORT\n\
+             BinaryDataByteOrderMSB = True\nHeaderSize = 3\nElementSpacing = 1 2 3\n\
+             Offset = -1 -2 -3\nTransformMatrix = 0 1 0 1 0 0 0 0 1\nElementDataFile = {name}\n"
+        ),
+    )
+    .unwrap();
+    let values: Vec<u16> = (0..8).map(|i| 300 * i).collect();
+    let mut bytes = vec![0xff; 3];
+    bytes.extend(values.iter().flat_map(|x| x.to_be_bytes()));
+    // trailing data is ignored
+    bytes.extend([0xff; 5]);
+    std::fs::write(&data, bytes).unwrap();
+
+    let volume = read_metaimage_file(&header);
+    std::fs::remove_file(&header).unwrap();
+    std::fs::remove_file(&data).unwrap();
+    let volume = volume.unwrap();
+    assert_eq!(volume.samples, Samples::U16(values));
+    assert_eq!(
+        volume.directions,
+        [[0., 1., 0.], [2., 0., 0.], [0., 0., 3.]]
+    );
+    assert_eq!(volume.origin, [-1., -2., -3.]);
+
+    // data in the same file, with a 2-dimensional image
+    let mut file =
+        b"NDims = 2\nDimSize = 3 2\nElementType = MET_UCHAR\nElementDataFile = LOCAL\n".to_vec();
+    file.extend(0..6);
+    let volume = read_metaimage(&file[..]).unwrap();
+    assert_eq!(volume.samples, Samples::U8((0..6).collect()));
+    assert_eq!(volume.shape, [3, 2, 1]);
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn metaimage_compressed() {
+    use std::io::Write;
+
+    let values: Vec<f32> = (0..8).map(|i| i as f32 / 2.).collect();
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    for x in &values {
+        encoder.write_all(&x.to_le_bytes()).unwrap();
+    }
+    let mut file = b"NDims = 3\nDimSize = 2 2 2\nElementType = MET_FLOAT\n\
+                     CompressedData = True\nElementDataFile = LOCAL\n"
+        .to_vec();
+    file.extend(encoder.finish().unwrap());
+    let volume = read_metaimage(&file[..]).unwrap();
+    assert_eq!(volume.samples, Samples::F32(values));
+}
+
+#[test]
+fn metaimage_bad_headers() {
+    let cases: [&[u8]; 8] = [
+        b"NDims = 3\nDimSize = 2 2 2\nElementType = MET_UCHAR\n",
+        b"NDims = 3\nDimSize = 2 2 2\nElementDataFile = LOCAL\n",
+        b"NDims = 4\nDimSize = 2 2 2 2\nElementType = MET_UCHAR\nElementDataFile = LOCAL\n",
+        b"NDims = 3\nDimSize = 2 2 -2\nElementType = MET_UCHAR\nElementDataFile = LOCAL\n",
+        b"NDims = 3\nDimSize = 2 2 2\nElementType = MET_INT\nElementDataFile = LOCAL\n",
+        // too few samples
+        b"NDims = 3\nDimSize = 2 2 2\nElementType = MET_UCHAR\nElementDataFile = LOCAL\n1234567",
+        // the number of samples overflows
+        b"NDims = 3\nDimSize = 4294967296 4294967296 4294967296\nElementType = MET_UCHAR\n\
+          ElementDataFile = LOCAL\n",
+        b"NDims = 3\nDimSize = 1e30 1 1\nElementType = MET_UCHAR\nElementDataFile = LOCAL\n",
+    ];
+    for file in cases {
+        let err = read_metaimage(file).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::InvalidData,
+            "{}",
+            String::from_utf8_lossy(file)
+        );
+    }
+}
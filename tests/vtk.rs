@@ -0,0 +1,221 @@
+extern crate isosurface;
+
+use isosurface::{
+    marching_tetrahedra, marching_triangles, write_vtk, write_vtk_isoline, Mesh, VtkFormat,
+    VtkOptions,
+};
+
+/// A sphere around the center of an 8³ grid, with the first two coordinates as data.
+fn sphere() -> Mesh<f64, (f64, f32)> {
+    let n = 8;
+    let mut u = Vec::new();
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let x = [i, j, k].map(|i| i as f64 - 3.5);
+                u.push((x[0] * x[0] + x[1] * x[1] + x[2] * x[2]).sqrt());
+            }
+        }
+    }
+    let mesh = marching_tetrahedra(&u, (n, n, n), 2.5);
+    Mesh {
+        data: mesh.verts.iter().map(|v| (v[0], v[1] as f32)).collect(),
+        verts: mesh.verts,
+        faces: mesh.faces,
+        normals: mesh.normals,
+    }
+}
+
+fn write(mesh: &Mesh<f64, (f64, f32)>, format: VtkFormat) -> Vec<u8> {
+    let options = VtkOptions {
+        format,
+        data_name: "my data",
+    };
+    let mut out = Vec::new();
+    write_vtk(&mut out, mesh, &options).unwrap();
+    out
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|w| w == needle.as_bytes())
+}
+
+/// The value of the attribute `name` in every element of the XML text.
+fn attributes<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let pattern = format!(" {name}=\"");
+    xml.match_indices(&pattern)
+        .map(|(i, _)| {
+            let value = &xml[i + pattern.len()..];
+            &value[..value.find('"').unwrap()]
+        })
+        .collect()
+}
+
+fn base64_decode(s: &str) -> Vec<u8> {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut r = Vec::new();
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0;
+        let mut len = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            if c != b'=' {
+                n |= (ALPHABET.iter().position(|&a| a == c).unwrap() as u32) << (18 - 6 * i);
+                len += 1;
+            }
+        }
+        r.extend_from_slice(&n.to_be_bytes()[1..len]);
+    }
+    r
+}
+
+fn block_size(block: &[u8]) -> usize {
+    u64::from_le_bytes(block[..8].try_into().unwrap()) as usize
+}
+
+#[test]
+fn legacy_counts() {
+    let mesh = sphere();
+    let (n, m) = (mesh.verts.len(), mesh.faces.len());
+
+    let out = write(&mesh, VtkFormat::LegacyAscii);
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[2], "ASCII");
+
+    let points = lines
+        .iter()
+        .position(|&l| l == format!("POINTS {n} double"));
+    let polygons = lines
+        .iter()
+        .position(|&l| l == format!("POLYGONS {m} {}", 4 * m));
+    let (points, polygons) = (points.unwrap(), polygons.unwrap());
+    assert_eq!(polygons, points + n + 1);
+    assert!(lines[polygons + 1..=polygons + m]
+        .iter()
+        .all(|l| l.starts_with("3 ") && l.split(' ').count() == 4));
+    assert_eq!(lines[polygons + m + 1], format!("POINT_DATA {n}"));
+
+    // the names are single tokens
+    assert!(lines.contains(&"SCALARS my_data_0 double 1"));
+    assert!(lines.contains(&"SCALARS my_data_1 float 1"));
+
+    let out = write(&mesh, VtkFormat::LegacyBinary);
+    assert!(contains(&out, "\nBINARY\n"));
+    assert!(contains(&out, &format!("\nPOINTS {n} double\n")));
+    assert!(contains(&out, &format!("\nPOLYGONS {m} {}\n", 4 * m)));
+    assert!(contains(&out, "\nSCALARS my_data_0 double 1\n"));
+}
+
+#[test]
+fn legacy_isoline_counts() {
+    let n = 9;
+    let u: Vec<f64> = (0..n * n)
+        .map(|i| {
+            let (x, y) = ((i / n) as f64 - 4., (i % n) as f64 - 4.);
+            (x * x + y * y).sqrt()
+        })
+        .collect();
+    let isoline = marching_triangles(&u, (n, n), 2.5);
+    let sizes: Vec<usize> = isoline.components().map(|c| c.len()).collect();
+    assert_eq!(sizes.len(), 1);
+
+    let options = VtkOptions {
+        format: VtkFormat::LegacyAscii,
+        ..VtkOptions::default()
+    };
+    let mut out = Vec::new();
+    write_vtk_isoline(&mut out, &isoline, &options).unwrap();
+    let text = String::from_utf8(out).unwrap();
+
+    assert!(text.contains(&format!("\nPOINTS {} ", sizes[0])));
+    assert!(text.contains(&format!("\nLINES 1 {}\n", sizes[0] + 1)));
+}
+
+#[test]
+fn xml_counts() {
+    let mesh = sphere();
+    for format in [VtkFormat::XmlAscii, VtkFormat::XmlBase64, VtkFormat::XmlRaw] {
+        let out = write(&mesh, format);
+        // the appended raw data is not valid UTF-8
+        let header = String::from_utf8_lossy(&out);
+        let header = header.split("<AppendedData").next().unwrap();
+
+        assert_eq!(
+            attributes(header, "NumberOfPoints"),
+            [mesh.verts.len().to_string()]
+        );
+        assert_eq!(
+            attributes(header, "NumberOfPolys"),
+            [mesh.faces.len().to_string()]
+        );
+        assert_eq!(attributes(header, "NumberOfLines"), ["0"]);
+        // the names are quoted, so they are kept
+        assert_eq!(
+            attributes(header, "Name"),
+            [
+                "Normals",
+                "my data_0",
+                "my data_1",
+                "Points",
+                "connectivity",
+                "offsets"
+            ]
+        );
+    }
+}
+
+/// The size of every array in bytes, in the order of the file.
+fn array_sizes(mesh: &Mesh<f64, (f64, f32)>) -> [usize; 6] {
+    let (n, m) = (mesh.verts.len(), mesh.faces.len());
+    [24 * n, 8 * n, 4 * n, 24 * n, 12 * m, 4 * m]
+}
+
+#[test]
+fn base64_block_sizes() {
+    let mesh = sphere();
+    let out = write(&mesh, VtkFormat::XmlBase64);
+    let text = String::from_utf8(out).unwrap();
+
+    let lines: Vec<&str> = text.lines().collect();
+    let blocks: Vec<Vec<u8>> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| l.starts_with("<DataArray") && l.ends_with(r#"format="binary">"#))
+        .map(|(i, _)| base64_decode(lines[i + 1]))
+        .collect();
+
+    assert_eq!(blocks.len(), 6);
+    for (block, size) in blocks.iter().zip(array_sizes(&mesh)) {
+        assert_eq!(block_size(block), size);
+        assert_eq!(block.len(), 8 + size);
+    }
+}
+
+#[test]
+fn raw_block_sizes() {
+    let mesh = sphere();
+    let out = write(&mesh, VtkFormat::XmlRaw);
+
+    let marker = br#"<AppendedData encoding="raw">_"#;
+    let start = out.windows(marker.len()).position(|w| w == marker).unwrap();
+    let header = std::str::from_utf8(&out[..start]).unwrap();
+    let appended = &out[start + marker.len()..];
+
+    let offsets: Vec<usize> = attributes(header, "offset")
+        .iter()
+        .map(|o| o.parse().unwrap())
+        .collect();
+    assert_eq!(offsets.len(), 6);
+
+    // the blocks follow each other without gaps
+    let mut end = 0;
+    for (&offset, size) in offsets.iter().zip(array_sizes(&mesh)) {
+        assert_eq!(offset, end);
+        assert_eq!(block_size(&appended[offset..]), size);
+        end = offset + 8 + size;
+    }
+    assert_eq!(&appended[end..], b"\n</AppendedData>\n</VTKFile>\n");
+}